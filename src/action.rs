@@ -1,16 +1,33 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::glob::GlobError;
 use super::matcher::*;
+use super::path::*;
 
 /// Trait for matching actions. When evaluating a policy, this is used to determine if
-/// the policy applies with respect to a concrete action.
+/// the policy applies with respect to a concrete action. Every `Matcher` is an
+/// action matcher for its target type.
 pub trait ActionMatch {
     /// The type of action matched by this implementation.
     type Action;
 
     /// Determine if a concrete action matches
-    fn test(&self, action: &Self::Action) -> bool;
+    fn test_action(&self, action: &Self::Action) -> bool;
+}
+
+impl<M> ActionMatch for M
+where
+    M: Matcher,
+{
+    type Action = M::Target;
+
+    fn test_action(&self, action: &Self::Action) -> bool {
+        self.test(action)
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct StrAction<'a>(pub &'a str);
 
 impl<'a> From<&'a str> for StrAction<'a> {
@@ -27,7 +44,222 @@ impl<'a> Matcher for StrAction<'a> {
     }
 }
 
+/// Trivial action represented by an owned string. Can also be used as a matcher.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord)]
+pub struct StringAction(pub String);
+
+impl StringAction {
+    pub fn new<S: Into<String>>(v: S) -> Self {
+        StringAction(v.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for StringAction {
+    fn from(v: &str) -> Self {
+        StringAction(v.into())
+    }
+}
+
+impl From<String> for StringAction {
+    fn from(v: String) -> Self {
+        StringAction(v)
+    }
+}
+
+impl<'a> From<StrAction<'a>> for StringAction {
+    fn from(v: StrAction<'a>) -> Self {
+        StringAction(v.0.into())
+    }
+}
+
+impl Matcher for StringAction {
+    type Target = Self;
+
+    fn test(&self, target: &Self::Target) -> bool {
+        self.0 == target.0
+    }
+}
+
+/// Error produced when parsing a qualified action or action matcher. Offsets
+/// are character offsets into the parsed string.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ParseActionError {
+    /// The segment starting at the given offset is empty e.g. `storage::get`.
+    EmptySegment(usize),
+    /// The matcher segment starting at the given offset is not a valid glob pattern.
+    InvalidPattern(usize, GlobError),
+}
+
+impl fmt::Display for ParseActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ParseActionError::*;
+        match self {
+            EmptySegment(at) => write!(f, "empty action segment at offset {}", at),
+            InvalidPattern(at, e) => {
+                write!(f, "invalid pattern in segment at offset {}: {}", at, e)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseActionError {}
+
+const NAMESPACE_SEPARATOR: char = ':';
+
+/// Private helper. Split an action into non-empty segments paired with their offsets.
+fn split_segments(s: &str) -> Result<Vec<(usize, &str)>, ParseActionError> {
+    let mut offset = 0;
+    s.split(NAMESPACE_SEPARATOR)
+        .map(|segment| {
+            let at = offset;
+            offset += segment.chars().count() + 1;
+            if segment.is_empty() {
+                Err(ParseActionError::EmptySegment(at))
+            } else {
+                Ok((at, segment))
+            }
+        })
+        .collect()
+}
+
+/// Action qualified by a hierarchy of namespaces, written as segments separated
+/// by `:` e.g. `storage:objects:get`.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct QualifiedAction(Path);
+
+impl QualifiedAction {
+    /// The namespace segments followed by the action name.
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.0.elems().iter().map(|e| e.as_str())
+    }
+}
+
+impl FromStr for QualifiedAction {
+    type Err = ParseActionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let segments = split_segments(s)?;
+        Ok(QualifiedAction(Path::new(
+            segments.into_iter().map(|(_, segment)| segment),
+        )))
+    }
+}
+
+impl fmt::Display for QualifiedAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments().enumerate() {
+            if i > 0 {
+                write!(f, "{}", NAMESPACE_SEPARATOR)?;
+            }
+            write!(f, "{}", segment)?;
+        }
+        Ok(())
+    }
+}
+
+/// Matcher for qualified actions in the style of cloud IAM. Each segment of
+/// the matcher is a literal or a glob pattern (e.g. `get*`) matching a single
+/// segment of the action. A final `*` segment matches one or more trailing
+/// segments so `storage:*` matches every action in the `storage` namespace
+/// and `*` matches every action.
+///
+/// # Examples
+///
+/// ```
+/// use authorization_core::action::*;
+/// use authorization_core::matcher::*;
+///
+/// let matcher: QualifiedActionMatcher = "storage:objects:*".parse().unwrap();
+///
+/// assert!(matcher.test(&"storage:objects:get".parse().unwrap()));
+/// assert!(!matcher.test(&"storage:buckets:get".parse().unwrap()));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct QualifiedActionMatcher(PathMatcher);
+
+impl QualifiedActionMatcher {
+    /// Private helper. Determine if the matcher ends in a namespace wildcard.
+    fn is_prefix(&self) -> bool {
+        self.0
+            .elems()
+            .ends_with(&[PathElemMatcher::ANY, PathElemMatcher::MULTI])
+    }
+}
+
+impl FromStr for QualifiedActionMatcher {
+    type Err = ParseActionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let segments = split_segments(s)?;
+        let mut elems = Vec::with_capacity(segments.len() + 1);
+        for (at, segment) in segments {
+            elems.push(match segment {
+                "!" => PathElemMatcher::NONE,
+                segment => PathElemMatcher::glob(segment)
+                    .map_err(|e| ParseActionError::InvalidPattern(at, e))?,
+            });
+        }
+        if elems.last() == Some(&PathElemMatcher::ANY) {
+            elems.push(PathElemMatcher::MULTI);
+        }
+        Ok(QualifiedActionMatcher(PathMatcher::new(elems)))
+    }
+}
+
+impl fmt::Display for QualifiedActionMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let elems = self.0.elems();
+        let elems = if self.is_prefix() {
+            &elems[..elems.len() - 1]
+        } else {
+            elems
+        };
+        for (i, elem) in elems.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", NAMESPACE_SEPARATOR)?;
+            }
+            write!(f, "{}", elem)?;
+        }
+        Ok(())
+    }
+}
+
+impl Matcher for QualifiedActionMatcher {
+    type Target = QualifiedAction;
+
+    fn test(&self, target: &Self::Target) -> bool {
+        self.0.test(&target.0)
+    }
+}
+
+impl ExtendedMatcher for QualifiedActionMatcher {
+    type Target = QualifiedAction;
+
+    /// Match a specific action
+    fn match_only<T: Into<Self::Target>>(target: T) -> Self {
+        QualifiedActionMatcher(target.into().0.into())
+    }
+
+    /// Match any action (i.e. test is const true)
+    fn match_any() -> Self {
+        QualifiedActionMatcher(PathMatcher::new(vec![
+            PathElemMatcher::ANY,
+            PathElemMatcher::MULTI,
+        ]))
+    }
+
+    /// match nothing (i.e. test is const false)
+    fn match_none() -> Self {
+        QualifiedActionMatcher(PathMatcher::new(vec![PathElemMatcher::NONE]))
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
 
     use super::*;
@@ -39,4 +271,128 @@ mod tests {
         assert_eq!(StrAction("abc").test(&action), true);
         assert_eq!(StrAction("xyz").test(&action), false);
     }
+
+    #[test]
+    fn test_string_action_matcher() {
+        let action = StringAction::new("abc");
+
+        assert!(StringAction::from("abc").test(&action));
+        assert!(!StringAction::from("xyz".to_string()).test(&action));
+        assert_eq!(StringAction::from(StrAction("abc")), action);
+    }
+
+    #[test]
+    fn test_matcher_is_action_match() {
+        let action = StrAction("abc");
+
+        assert!(StrAction("abc").test_action(&action));
+        assert!(!StrAction("xyz").test_action(&action));
+    }
+
+    fn action(s: &str) -> QualifiedAction {
+        s.parse().unwrap()
+    }
+
+    fn matcher(s: &str) -> QualifiedActionMatcher {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_qualified_action() {
+        let actual = action("storage:objects:get");
+
+        assert_eq!(
+            actual.segments().collect::<Vec<_>>(),
+            vec!["storage", "objects", "get"]
+        );
+        assert_eq!(actual.to_string(), "storage:objects:get");
+        assert_eq!(
+            "storage::get".parse::<QualifiedAction>(),
+            Err(ParseActionError::EmptySegment(8))
+        );
+        assert_eq!(
+            "".parse::<QualifiedAction>(),
+            Err(ParseActionError::EmptySegment(0))
+        );
+    }
+
+    #[test]
+    fn test_qualified_action_matcher_exact() {
+        let m = matcher("storage:objects:get");
+
+        assert!(m.test(&action("storage:objects:get")));
+        assert!(!m.test(&action("storage:objects:list")));
+        assert!(!m.test(&action("storage:objects")));
+        assert!(!m.test(&action("storage:objects:get:more")));
+    }
+
+    #[test]
+    fn test_qualified_action_matcher_prefix() {
+        let m = matcher("storage:*");
+
+        assert!(m.test(&action("storage:list")));
+        assert!(m.test(&action("storage:objects:get")));
+        assert!(!m.test(&action("storage")));
+        assert!(!m.test(&action("compute:list")));
+
+        assert!(matcher("*").test(&action("anything:at:all")));
+    }
+
+    #[test]
+    fn test_qualified_action_matcher_wildcard_segment() {
+        let m = matcher("storage:*:get");
+
+        assert!(m.test(&action("storage:objects:get")));
+        assert!(m.test(&action("storage:buckets:get")));
+        assert!(!m.test(&action("storage:objects:list")));
+        assert!(!m.test(&action("storage:a:b:get")));
+    }
+
+    #[test]
+    fn test_qualified_action_matcher_glob_segment() {
+        let m = matcher("storage:objects:get*");
+
+        assert!(m.test(&action("storage:objects:get")));
+        assert!(m.test(&action("storage:objects:getIamPolicy")));
+        assert!(!m.test(&action("storage:objects:list")));
+    }
+
+    #[test]
+    fn test_qualified_action_matcher_errors() {
+        assert_eq!(
+            "storage:[a".parse::<QualifiedActionMatcher>(),
+            Err(ParseActionError::InvalidPattern(
+                8,
+                GlobError::UnterminatedClass(0)
+            ))
+        );
+        assert_eq!(
+            "storage:".parse::<QualifiedActionMatcher>(),
+            Err(ParseActionError::EmptySegment(8))
+        );
+    }
+
+    #[test]
+    fn test_qualified_action_matcher_display_round_trip() {
+        for s in &["storage:*", "*", "storage:*:get", "a:get*", "!", "a:\\*"] {
+            let m = matcher(s);
+            assert_eq!(m.to_string(), *s);
+            assert_eq!(m.to_string().parse(), Ok(m));
+        }
+    }
+
+    #[test]
+    fn test_qualified_action_matcher_extended() {
+        let m = QualifiedActionMatcher::match_only(action("storage:objects:get"));
+        assert!(m.test(&action("storage:objects:get")));
+        assert!(!m.test(&action("storage:objects:list")));
+
+        let m = QualifiedActionMatcher::match_any();
+        assert!(m.test(&action("storage:objects:get")));
+        assert!(m.test(&action("get")));
+        assert_eq!(m, matcher("*"));
+
+        let m = QualifiedActionMatcher::match_none();
+        assert!(!m.test(&action("get")));
+    }
 }
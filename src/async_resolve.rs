@@ -0,0 +1,375 @@
+//! Asynchronous resolution of dependent effects.
+//!
+//! `DependentEffect::resolve_async` and `resolve_all_async` produce futures that
+//! test conditions through an `AsyncEnvironment`. The constituents of an
+//! `Aggregate` or `Disjoint` are polled together so independent conditions are
+//! evaluated concurrently without spawning tasks or depending on an executor.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use super::dependent_effect::*;
+use super::effect::*;
+use super::environment::*;
+
+/// Future resolving a dependent effect. Created by `DependentEffect::resolve_async`.
+#[must_use = "futures do nothing unless polled"]
+pub struct Resolve<'a, Env>
+where
+    Env: AsyncEnvironment + 'a,
+{
+    state: State<'a, Env>,
+}
+
+/// Private helper. Resolution state mirroring the dependent effect structure.
+enum State<'a, Env>
+where
+    Env: AsyncEnvironment + 'a,
+{
+    Ready(Option<Result<ComputedEffect, Env::Err>>),
+    Atomic(Effect, Pin<Box<Env::Test<'a>>>),
+    Aggregate(ResolveAll<'a, Env>),
+    Disjoint(ResolveAll<'a, Env>),
+    Combined(&'a Algorithm, ResolveAll<'a, Env>),
+    Prioritized(Vec<Priority>, ResolveAll<'a, Env>),
+}
+
+// condition tests are boxed and nothing else is pinned structurally
+impl<'a, Env> Unpin for Resolve<'a, Env> where Env: AsyncEnvironment + 'a {}
+
+impl<'a, Env> Future for Resolve<'a, Env>
+where
+    Env: AsyncEnvironment + 'a,
+{
+    type Output = Result<ComputedEffect, Env::Err>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match &mut self.get_mut().state {
+            State::Ready(result) => {
+                Poll::Ready(result.take().expect("`Resolve` polled after completion"))
+            }
+            State::Atomic(eff, test) => test.as_mut().poll(cx).map(|matched| {
+                if matched? {
+                    Ok(Some(*eff).into())
+                } else {
+                    Ok(SILENT)
+                }
+            }),
+            State::Aggregate(all) => Pin::new(all)
+                .poll(cx)
+                .map(|resolved| resolved.map(combine_non_strict)),
+            State::Disjoint(all) => Pin::new(all)
+                .poll(cx)
+                .map(|resolved| resolved.map(combine_strict)),
+            State::Combined(algorithm, all) => Pin::new(all)
+                .poll(cx)
+                .map(|resolved| resolved.map(|effs| algorithm.combine(&effs))),
+            State::Prioritized(priorities, all) => Pin::new(all).poll(cx).map(|resolved| {
+                resolved.map(|effs| combine_prioritized(priorities.iter().copied().zip(effs)))
+            }),
+        }
+    }
+}
+
+/// Future resolving several dependent effects concurrently. Created by
+/// `resolve_all_async`.
+///
+/// Completes with the first error encountered, dropping the resolutions still
+/// pending. When several conditions fail, which error is reported depends on
+/// the order in which their tests complete.
+#[must_use = "futures do nothing unless polled"]
+pub struct ResolveAll<'a, Env>
+where
+    Env: AsyncEnvironment + 'a,
+{
+    pending: Vec<Resolve<'a, Env>>,
+    resolved: Vec<Option<ComputedEffect>>,
+}
+
+impl<'a, Env> Unpin for ResolveAll<'a, Env> where Env: AsyncEnvironment + 'a {}
+
+impl<'a, Env> Future for ResolveAll<'a, Env>
+where
+    Env: AsyncEnvironment + 'a,
+{
+    type Output = Result<Vec<ComputedEffect>, Env::Err>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        for (resolution, resolved) in this.pending.iter_mut().zip(this.resolved.iter_mut()) {
+            if resolved.is_none() {
+                if let Poll::Ready(effect) = Pin::new(resolution).poll(cx) {
+                    *resolved = Some(effect?);
+                }
+            }
+        }
+        if this.resolved.iter().all(Option::is_some) {
+            this.pending.clear();
+            Poll::Ready(Ok(this.resolved.drain(..).flatten().collect()))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl<CExp> DependentEffect<CExp> {
+    /// Evaluate dependent effect in an asynchronous environmental context.
+    /// Conditions of aggregated effects are tested concurrently.
+    pub fn resolve_async<'a, Env>(&'a self, environment: &'a Env) -> Resolve<'a, Env>
+    where
+        Env: AsyncEnvironment<CExp = CExp>,
+    {
+        use DependentEffect::*;
+        let state = match self {
+            Silent => State::Ready(Some(Ok(SILENT))),
+            Fixed(eff) => State::Ready(Some(Ok(Some(*eff).into()))),
+            Atomic(eff, cexp) => {
+                State::Atomic(*eff, Box::pin(environment.test_condition_async(cexp)))
+            }
+            Aggregate(effs) => State::Aggregate(resolve_all_async(effs.iter(), environment)),
+            Disjoint(effs) => State::Disjoint(resolve_all_async(effs.iter(), environment)),
+            Combined(algorithm, effs) => {
+                State::Combined(algorithm, resolve_all_async(effs.iter(), environment))
+            }
+            Prioritized(effs) => State::Prioritized(
+                effs.iter().map(|(p, _)| *p).collect(),
+                resolve_all_async(effs.iter().map(|(_, e)| e), environment),
+            ),
+            Obligated(_, eff) | Identified(_, eff) => return eff.resolve_async(environment),
+        };
+        Resolve { state }
+    }
+}
+
+/// Asynchronous counterpart of `resolve_all`. The effects are resolved concurrently
+/// and the results are in the order of the effects.
+pub fn resolve_all_async<'a, CExp: 'a, Env>(
+    perms: impl Iterator<Item = &'a DependentEffect<CExp>>,
+    environment: &'a Env,
+) -> ResolveAll<'a, Env>
+where
+    Env: AsyncEnvironment<CExp = CExp>,
+{
+    let pending: Vec<_> = perms.map(|p| p.resolve_async(environment)).collect();
+    ResolveAll {
+        resolved: vec![None; pending.len()],
+        pending,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::{Cell, RefCell};
+    use std::pin::pin;
+    use std::sync::Arc;
+    use std::task::{Wake, Waker};
+    use std::thread::{self, Thread};
+
+    use super::*;
+
+    /// Run a future to completion on the current thread.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct Unpark(Thread);
+
+        impl Wake for Unpark {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    /// Environment whose tests each wait for one poll. A condition matches if it
+    /// equals the environment value and fails if it is zero. Records how many
+    /// tests had started whenever one completes.
+    struct DelayedEnv {
+        value: u32,
+        started: Cell<usize>,
+        completed: RefCell<Vec<usize>>,
+    }
+
+    impl DelayedEnv {
+        fn new(value: u32) -> Self {
+            DelayedEnv {
+                value,
+                started: Cell::new(0),
+                completed: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    /// Future pending on its first poll.
+    struct Delayed<'a> {
+        env: &'a DelayedEnv,
+        polled: bool,
+    }
+
+    impl Future for Delayed<'_> {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            if !self.polled {
+                self.polled = true;
+                self.env.started.set(self.env.started.get() + 1);
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.env.completed.borrow_mut().push(self.env.started.get());
+            Poll::Ready(())
+        }
+    }
+
+    impl AsyncEnvironment for DelayedEnv {
+        type Err = ();
+        type CExp = u32;
+        type Test<'a> = Pin<Box<dyn Future<Output = Result<bool, ()>> + 'a>>;
+
+        fn test_condition_async<'a>(&'a self, exp: &'a u32) -> Self::Test<'a> {
+            Box::pin(async move {
+                Delayed {
+                    env: self,
+                    polled: false,
+                }
+                .await;
+                match *exp {
+                    0 => Err(()),
+                    exp => Ok(exp == self.value),
+                }
+            })
+        }
+    }
+
+    fn sample() -> DependentEffect<u32> {
+        use DependentEffect::*;
+        Disjoint(vec![
+            Aggregate(vec![
+                Atomic(Effect::ALLOW, 1),
+                Atomic(Effect::DENY, 2),
+                Fixed(Effect::ALLOW),
+            ]),
+            Aggregate(vec![Atomic(Effect::ALLOW, 1), Atomic(Effect::ALLOW, 2)]),
+        ])
+    }
+
+    #[test]
+    fn test_matches_synchronous_resolution() {
+        let effect = sample();
+
+        for value in 0..5u32 {
+            let actual = block_on(effect.resolve_async(&value));
+
+            let expected = effect.resolve(&value);
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_resolve_async() {
+        let effect = sample();
+
+        assert_eq!(
+            block_on(effect.resolve_async(&DelayedEnv::new(1))),
+            Ok(ALLOW)
+        );
+        assert_eq!(
+            block_on(effect.resolve_async(&DelayedEnv::new(2))),
+            Ok(DENY)
+        );
+        assert_eq!(
+            block_on(effect.resolve_async(&DelayedEnv::new(3))),
+            Ok(SILENT)
+        );
+    }
+
+    #[test]
+    fn test_conditions_are_tested_concurrently() {
+        let env = DelayedEnv::new(1);
+
+        let actual = block_on(sample().resolve_async(&env));
+
+        assert_eq!(actual, Ok(ALLOW));
+
+        // every test started before any completed
+        assert_eq!(*env.completed.borrow(), vec![4, 4, 4, 4]);
+    }
+
+    #[test]
+    fn test_resolve_combined_async() {
+        use DependentEffect::*;
+        let effect = Combined(
+            Algorithm::FirstApplicable,
+            vec![Atomic(Effect::DENY, 2), Atomic(Effect::ALLOW, 1)],
+        );
+
+        for value in 1..4u32 {
+            let actual = block_on(effect.resolve_async(&DelayedEnv::new(value)));
+
+            assert_eq!(actual, effect.resolve(&value));
+        }
+    }
+
+    #[test]
+    fn test_resolve_prioritized_async() {
+        use DependentEffect::*;
+        let effect = Prioritized(vec![
+            (1, Atomic(Effect::DENY, 1)),
+            (2, Atomic(Effect::ALLOW, 2)),
+            (1, Atomic(Effect::ALLOW, 3)),
+        ]);
+
+        for value in 1..5u32 {
+            let actual = block_on(effect.resolve_async(&DelayedEnv::new(value)));
+
+            assert_eq!(actual, effect.resolve(&value));
+        }
+    }
+
+    #[test]
+    fn test_resolve_async_error() {
+        use DependentEffect::*;
+        let env = DelayedEnv::new(1);
+        let effect = Aggregate(vec![Atomic(Effect::ALLOW, 1), Atomic(Effect::DENY, 0)]);
+
+        assert_eq!(block_on(effect.resolve_async(&env)), Err(()));
+    }
+
+    #[test]
+    fn test_resolve_all_async() {
+        use DependentEffect::*;
+        let perms = [
+            Atomic(Effect::ALLOW, 1),
+            Atomic(Effect::DENY, 2),
+            Fixed(Effect::DENY),
+            Silent,
+        ];
+        let env = DelayedEnv::new(1);
+
+        let actual = block_on(resolve_all_async(perms.iter(), &env));
+
+        assert_eq!(actual, Ok(vec![ALLOW, SILENT, DENY, SILENT]));
+        assert_eq!(*env.completed.borrow(), vec![2, 2]);
+
+        let empty: [DependentEffect<u32>; 0] = [];
+        assert_eq!(block_on(resolve_all_async(empty.iter(), &env)), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_resolution_is_send_for_send_environments() {
+        fn assert_send<T: Send>(_: T) {}
+
+        let effect = sample();
+        assert_send(effect.resolve_async(&1u32));
+        assert_send(resolve_all_async([effect].iter(), &1u32));
+    }
+}
@@ -0,0 +1,632 @@
+//! Standard condition expressions for attribute based access control.
+//!
+//! A `Condition` is evaluated against named attributes, e.g. `resource.owner`
+//! or `principal.id`, supplied by an `AttributeEnvironment`. It can be used as
+//! the `CExp` of a `Policy` so that no custom expression type is needed.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+
+use super::environment::*;
+use super::network::Cidr;
+
+/// Attribute or literal value.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Bool(bool),
+    Num(f64),
+    Str(String),
+    Set(Vec<Value>),
+    /// Block of network addresses. A single address is a block of one.
+    Cidr(Cidr),
+}
+
+impl Value {
+    /// Name of the value's type, used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        use Value::*;
+        match self {
+            Bool(_) => "boolean",
+            Num(_) => "number",
+            Str(_) => "string",
+            Set(_) => "set",
+            Cidr(_) => "address block",
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Self {
+        Value::Bool(v)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Self {
+        Value::Num(v)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(v: i32) -> Self {
+        Value::Num(v.into())
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::Str(v.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Value::Str(v)
+    }
+}
+
+impl From<Cidr> for Value {
+    fn from(v: Cidr) -> Self {
+        Value::Cidr(v)
+    }
+}
+
+impl<V> From<Vec<V>> for Value
+where
+    V: Into<Value>,
+{
+    fn from(v: Vec<V>) -> Self {
+        Value::Set(v.into_iter().map(|v| v.into()).collect())
+    }
+}
+
+/// Operand of a comparison or membership test.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Operand {
+    /// The value of a named attribute
+    Attr(String),
+    /// A literal value
+    Const(Value),
+}
+
+impl Operand {
+    /// Reference a named attribute.
+    pub fn attr<N: Into<String>>(name: N) -> Self {
+        Operand::Attr(name.into())
+    }
+
+    /// Literal value.
+    pub fn value<V: Into<Value>>(value: V) -> Self {
+        Operand::Const(value.into())
+    }
+}
+
+/// Comparison operator.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Condition expression.
+///
+/// # Examples
+///
+/// ```
+/// use authorization_core::condition::*;
+/// use authorization_core::environment::*;
+///
+/// let condition = Condition::And(vec![
+///     Condition::Exists("resource.owner".into()),
+///     Condition::Compare(
+///         Comparison::Eq,
+///         Operand::attr("resource.owner"),
+///         Operand::attr("principal.id"),
+///     ),
+/// ]);
+///
+/// let env: AttributeEnvironment = vec![
+///     ("resource.owner", "alice"),
+///     ("principal.id", "alice"),
+/// ].into_iter().collect();
+///
+/// assert_eq!(env.test_condition(&condition), Ok(true));
+/// assert_eq!(AttributeEnvironment::new().test_condition(&condition), Ok(false));
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub enum Condition {
+    /// Constant truth value
+    Const(bool),
+    /// Holds if every constituent holds. Constituents are evaluated in order
+    /// and evaluation stops at the first that does not hold.
+    And(Vec<Condition>),
+    /// Holds if any constituent holds. Constituents are evaluated in order
+    /// and evaluation stops at the first that holds.
+    Or(Vec<Condition>),
+    /// Holds if the constituent does not hold.
+    Not(Box<Condition>),
+    /// Compare two operands. Values of different types are never equal. Only
+    /// numbers and strings can be ordered.
+    Compare(Comparison, Operand, Operand),
+    /// Holds if the first operand is an element of the second, which must be a
+    /// set or an address block. An address, given as a string or a single
+    /// address block, is an element of every block containing it, including
+    /// blocks in a set.
+    In(Operand, Operand),
+    /// Holds if the named attribute is defined.
+    Exists(String),
+}
+
+/// Error produced when a condition cannot be evaluated.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ConditionError {
+    /// The named attribute is referenced but not defined.
+    UndefinedAttribute(String),
+    /// An operation was applied to a value of the wrong type. Holds a
+    /// description of the operation and the type names.
+    TypeMismatch(String),
+}
+
+impl fmt::Display for ConditionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ConditionError::*;
+        match self {
+            UndefinedAttribute(name) => write!(f, "undefined attribute: {}", name),
+            TypeMismatch(msg) => write!(f, "type mismatch: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ConditionError {}
+
+/// Environment evaluating `Condition`s against a set of named attributes.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct AttributeEnvironment {
+    attributes: HashMap<String, Value>,
+}
+
+impl AttributeEnvironment {
+    /// Create an environment with no attributes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define an attribute, replacing any previous value.
+    pub fn insert<N, V>(&mut self, name: N, value: V)
+    where
+        N: Into<String>,
+        V: Into<Value>,
+    {
+        self.attributes.insert(name.into(), value.into());
+    }
+
+    /// Define an attribute, replacing any previous value, and return the environment.
+    pub fn with<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: Into<String>,
+        V: Into<Value>,
+    {
+        self.insert(name, value);
+        self
+    }
+
+    /// Look up an attribute.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.attributes.get(name)
+    }
+
+    /// Private helper. Resolve an operand to a value.
+    fn operand<'a>(&'a self, operand: &'a Operand) -> Result<&'a Value, ConditionError> {
+        match operand {
+            Operand::Const(v) => Ok(v),
+            Operand::Attr(name) => self
+                .get(name)
+                .ok_or_else(|| ConditionError::UndefinedAttribute(name.clone())),
+        }
+    }
+
+    /// Evaluate a condition.
+    pub fn evaluate(&self, condition: &Condition) -> Result<bool, ConditionError> {
+        use Condition::*;
+        match condition {
+            Const(b) => Ok(*b),
+            And(cs) => {
+                for c in cs {
+                    if !self.evaluate(c)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Or(cs) => {
+                for c in cs {
+                    if self.evaluate(c)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Not(c) => self.evaluate(c).map(|b| !b),
+            Compare(op, l, r) => compare(*op, self.operand(l)?, self.operand(r)?),
+            In(elem, set) => match self.operand(set)? {
+                Value::Set(values) => {
+                    let elem = self.operand(elem)?;
+                    Ok(values.iter().any(|v| match v {
+                        Value::Cidr(block) => within(elem, block) == Some(true),
+                        v => v == elem,
+                    }))
+                }
+                Value::Cidr(block) => {
+                    let elem = self.operand(elem)?;
+                    within(elem, block).ok_or_else(|| {
+                        ConditionError::TypeMismatch(format!(
+                            "membership in an address block requires an address, found {}",
+                            elem.type_name()
+                        ))
+                    })
+                }
+                other => Err(ConditionError::TypeMismatch(format!(
+                    "membership requires a set or address block, found {}",
+                    other.type_name()
+                ))),
+            },
+            Exists(name) => Ok(self.attributes.contains_key(name)),
+        }
+    }
+}
+
+/// Private helper. Determine if a value is an address in a block. Strings are
+/// parsed as addresses and blocks must be contained in the block. Produces
+/// `None` if the value is not an address.
+fn within(value: &Value, block: &Cidr) -> Option<bool> {
+    match value {
+        Value::Str(s) => s.parse::<IpAddr>().ok().map(|a| block.contains(&a)),
+        Value::Cidr(c) => Some(c.prefix() >= block.prefix() && block.contains(&c.network())),
+        _ => None,
+    }
+}
+
+/// Private helper. Determine if an address block equals another value. An
+/// address equals a block holding only that address, as for `within`, and
+/// equality with a wider block or a value that is not an address is a type
+/// mismatch. Produces `None` if neither value is an address block.
+fn equal_address(l: &Value, r: &Value) -> Option<Result<bool, ConditionError>> {
+    let (block, other) = match (l, r) {
+        (Value::Cidr(block), other) | (other, Value::Cidr(block)) => (block, other),
+        _ => return None,
+    };
+    let host = if block.network().is_ipv4() { 32 } else { 128 };
+    Some(match other {
+        Value::Cidr(c) => Ok(c == block),
+        _ if block.prefix() != host => Err(ConditionError::TypeMismatch(format!(
+            "equality requires a single address, found block {}",
+            block
+        ))),
+        _ => within(other, block).ok_or_else(|| {
+            ConditionError::TypeMismatch(format!(
+                "cannot compare an address with {}",
+                other.type_name()
+            ))
+        }),
+    })
+}
+
+/// Private helper. Apply a comparison to two values.
+fn compare(op: Comparison, l: &Value, r: &Value) -> Result<bool, ConditionError> {
+    use std::cmp::Ordering;
+    use Comparison::*;
+
+    if let (Eq | Ne, Some(equal)) = (op, equal_address(l, r)) {
+        return equal.map(|equal| equal == (op == Eq));
+    }
+    let ordering = match (l, r) {
+        (Value::Num(a), Value::Num(b)) => a.partial_cmp(b),
+        (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+    match (op, ordering) {
+        (Eq, _) => Ok(l == r),
+        (Ne, _) => Ok(l != r),
+        (Lt, Some(o)) => Ok(o == Ordering::Less),
+        (Le, Some(o)) => Ok(o != Ordering::Greater),
+        (Gt, Some(o)) => Ok(o == Ordering::Greater),
+        (Ge, Some(o)) => Ok(o != Ordering::Less),
+        // comparable types with no ordering (NaN) never satisfy an ordering
+        (_, None) if matches!((l, r), (Value::Num(_), Value::Num(_))) => Ok(false),
+        (op, None) => Err(ConditionError::TypeMismatch(format!(
+            "cannot apply {:?} to {} and {}",
+            op,
+            l.type_name(),
+            r.type_name()
+        ))),
+    }
+}
+
+impl<N, V> FromIterator<(N, V)> for AttributeEnvironment
+where
+    N: Into<String>,
+    V: Into<Value>,
+{
+    fn from_iter<I: IntoIterator<Item = (N, V)>>(iter: I) -> Self {
+        let mut env = AttributeEnvironment::new();
+        for (name, value) in iter {
+            env.insert(name, value);
+        }
+        env
+    }
+}
+
+impl Environment for AttributeEnvironment {
+    type Err = ConditionError;
+    type CExp = Condition;
+
+    fn test_condition(&self, exp: &Self::CExp) -> Result<bool, Self::Err> {
+        self.evaluate(exp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dependent_effect::*;
+    use crate::effect::*;
+    use crate::policy::*;
+    use crate::resource::*;
+
+    fn env() -> AttributeEnvironment {
+        AttributeEnvironment::new()
+            .with("principal.id", "alice")
+            .with("principal.groups", vec!["dev", "ops"])
+            .with("principal.admin", false)
+            .with("resource.owner", "alice")
+            .with("resource.size", 42)
+    }
+
+    fn attr(name: &str) -> Operand {
+        Operand::attr(name)
+    }
+
+    fn val<V: Into<Value>>(v: V) -> Operand {
+        Operand::value(v)
+    }
+
+    fn cmp(op: Comparison, l: Operand, r: Operand) -> Condition {
+        Condition::Compare(op, l, r)
+    }
+
+    #[test]
+    fn test_const() {
+        assert_eq!(env().evaluate(&Condition::Const(true)), Ok(true));
+        assert_eq!(env().evaluate(&Condition::Const(false)), Ok(false));
+    }
+
+    #[test]
+    fn test_equality() {
+        use Comparison::*;
+        let env = env();
+
+        assert_eq!(
+            env.evaluate(&cmp(Eq, attr("principal.id"), attr("resource.owner"))),
+            Ok(true)
+        );
+        assert_eq!(
+            env.evaluate(&cmp(Eq, attr("principal.id"), val("bob"))),
+            Ok(false)
+        );
+        assert_eq!(
+            env.evaluate(&cmp(Ne, attr("principal.id"), val("bob"))),
+            Ok(true)
+        );
+        assert_eq!(
+            env.evaluate(&cmp(Eq, attr("principal.admin"), val(false))),
+            Ok(true)
+        );
+        assert_eq!(
+            env.evaluate(&cmp(Eq, attr("resource.size"), val(42))),
+            Ok(true)
+        );
+        // different types are never equal
+        assert_eq!(
+            env.evaluate(&cmp(Eq, attr("resource.size"), val("42"))),
+            Ok(false)
+        );
+        assert_eq!(
+            env.evaluate(&cmp(Ne, attr("resource.size"), val("42"))),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn test_ordering() {
+        use Comparison::*;
+        let env = env();
+
+        assert_eq!(
+            env.evaluate(&cmp(Lt, attr("resource.size"), val(100))),
+            Ok(true)
+        );
+        assert_eq!(
+            env.evaluate(&cmp(Le, attr("resource.size"), val(42))),
+            Ok(true)
+        );
+        assert_eq!(
+            env.evaluate(&cmp(Gt, attr("resource.size"), val(42))),
+            Ok(false)
+        );
+        assert_eq!(
+            env.evaluate(&cmp(Ge, attr("resource.size"), val(42.5))),
+            Ok(false)
+        );
+        assert_eq!(env.evaluate(&cmp(Lt, val("abc"), val("abd"))), Ok(true));
+        assert_eq!(env.evaluate(&cmp(Lt, val(f64::NAN), val(1))), Ok(false));
+    }
+
+    #[test]
+    fn test_ordering_type_mismatch() {
+        let actual = env().evaluate(&cmp(Comparison::Lt, attr("principal.admin"), val(1)));
+
+        assert_eq!(
+            actual,
+            Err(ConditionError::TypeMismatch(
+                "cannot apply Lt to boolean and number".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_in() {
+        let env = env();
+
+        let c = Condition::In(val("ops"), attr("principal.groups"));
+        assert_eq!(env.evaluate(&c), Ok(true));
+
+        let c = Condition::In(val("hr"), attr("principal.groups"));
+        assert_eq!(env.evaluate(&c), Ok(false));
+
+        let c = Condition::In(attr("principal.id"), val(vec!["alice", "bob"]));
+        assert_eq!(env.evaluate(&c), Ok(true));
+
+        let c = Condition::In(val("a"), attr("principal.id"));
+        assert!(matches!(
+            env.evaluate(&c),
+            Err(ConditionError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_in_address_block() {
+        let block = |s: &str| val(s.parse::<Cidr>().unwrap());
+        let env = env().with("request.ip", "10.1.2.3");
+
+        let c = Condition::In(attr("request.ip"), block("10.0.0.0/8"));
+        assert_eq!(env.evaluate(&c), Ok(true));
+
+        let c = Condition::In(attr("request.ip"), block("192.168.0.0/16"));
+        assert_eq!(env.evaluate(&c), Ok(false));
+
+        let c = Condition::In(block("10.1.0.0/16"), block("10.0.0.0/8"));
+        assert_eq!(env.evaluate(&c), Ok(true));
+
+        let c = Condition::In(block("10.0.0.0/8"), block("10.1.0.0/16"));
+        assert_eq!(env.evaluate(&c), Ok(false));
+
+        let blocks = Operand::Const(Value::Set(vec![
+            Value::Cidr("192.168.0.0/16".parse().unwrap()),
+            Value::Cidr("10.0.0.0/8".parse().unwrap()),
+        ]));
+        let c = Condition::In(attr("request.ip"), blocks);
+        assert_eq!(env.evaluate(&c), Ok(true));
+
+        let c = Condition::In(attr("principal.id"), block("10.0.0.0/8"));
+        assert!(matches!(
+            env.evaluate(&c),
+            Err(ConditionError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_address_equality() {
+        use Comparison::*;
+        let block = |s: &str| val(s.parse::<Cidr>().unwrap());
+        let env = env()
+            .with("request.ip", "10.0.0.1")
+            .with("request.ip6", "::ffff:10.0.0.1");
+
+        let c = cmp(Eq, attr("request.ip"), block("10.0.0.1/32"));
+        assert_eq!(env.evaluate(&c), Ok(true));
+        let c = cmp(Ne, attr("request.ip"), block("10.0.0.1/32"));
+        assert_eq!(env.evaluate(&c), Ok(false));
+        let c = cmp(Eq, block("10.0.0.2/32"), attr("request.ip"));
+        assert_eq!(env.evaluate(&c), Ok(false));
+        let c = cmp(Ne, block("10.0.0.2/32"), attr("request.ip"));
+        assert_eq!(env.evaluate(&c), Ok(true));
+        let c = cmp(Eq, attr("request.ip6"), block("10.0.0.1/32"));
+        assert_eq!(env.evaluate(&c), Ok(true));
+
+        for op in [Eq, Ne] {
+            let c = cmp(op, attr("request.ip"), block("10.0.0.0/8"));
+            assert!(matches!(
+                env.evaluate(&c),
+                Err(ConditionError::TypeMismatch(_))
+            ));
+            let c = cmp(op, attr("principal.id"), block("10.0.0.1/32"));
+            assert!(matches!(
+                env.evaluate(&c),
+                Err(ConditionError::TypeMismatch(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_exists() {
+        let env = env();
+
+        assert_eq!(
+            env.evaluate(&Condition::Exists("principal.id".into())),
+            Ok(true)
+        );
+        assert_eq!(
+            env.evaluate(&Condition::Exists("principal.x".into())),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn test_undefined_attribute() {
+        let actual = env().evaluate(&cmp(Comparison::Eq, attr("nope"), val(1)));
+
+        assert_eq!(
+            actual,
+            Err(ConditionError::UndefinedAttribute("nope".into()))
+        );
+    }
+
+    #[test]
+    fn test_boolean_operators() {
+        use Condition::*;
+        let env = env();
+        let t = || Const(true);
+        let f = || Const(false);
+        let undefined = || Exists("x".into());
+        let error = || cmp(Comparison::Eq, attr("nope"), val(1));
+
+        assert_eq!(env.evaluate(&And(vec![])), Ok(true));
+        assert_eq!(env.evaluate(&And(vec![t(), t()])), Ok(true));
+        assert_eq!(env.evaluate(&And(vec![t(), undefined()])), Ok(false));
+        assert_eq!(env.evaluate(&Or(vec![])), Ok(false));
+        assert_eq!(env.evaluate(&Or(vec![f(), t()])), Ok(true));
+        assert_eq!(env.evaluate(&Or(vec![f(), f()])), Ok(false));
+        assert_eq!(env.evaluate(&Not(Box::new(f()))), Ok(true));
+
+        // evaluation stops once the result is known
+        assert_eq!(env.evaluate(&And(vec![f(), error()])), Ok(false));
+        assert_eq!(env.evaluate(&Or(vec![t(), error()])), Ok(true));
+        assert!(env.evaluate(&And(vec![t(), error()])).is_err());
+    }
+
+    #[test]
+    fn test_policy_with_conditions() {
+        let owner_only = cmp(Comparison::Eq, attr("resource.owner"), attr("principal.id"));
+        let policy = Policy::Conditional(
+            StrResource("doc"),
+            StrResource("read"),
+            Effect::ALLOW,
+            owner_only,
+        );
+
+        let effect = policy.apply(&"doc".into(), &"read".into());
+
+        assert_eq!(effect.resolve(&env()), Ok(ALLOW));
+        assert_eq!(
+            effect.resolve(&env().with("principal.id", "bob")),
+            Ok(SILENT)
+        );
+        assert!(matches!(effect, DependentEffect::Atomic(Effect::ALLOW, _)));
+    }
+}
@@ -0,0 +1,925 @@
+//! Text syntax for `Condition`s.
+//!
+//! # Grammar
+//!
+//! ```text
+//! condition  := or
+//! or         := and ( "||" and )*
+//! and        := unary ( "&&" unary )*
+//! unary      := "!" unary | "(" condition ")" | "exists" "(" attribute ")"
+//!             | operand ( comparison operand | "in" operand )?
+//! comparison := "==" | "!=" | "<" | "<=" | ">" | ">="
+//! operand    := attribute | literal
+//! literal    := "true" | "false" | number | string | address
+//!             | "[" ( literal ( "," literal )* )? "]"
+//! address    := ( ipv4 | ipv6 ) ( "/" prefix )?
+//! ```
+//!
+//! Attributes are dotted names such as `resource.owner`; each part starts with
+//! a letter or `_` followed by letters, digits or `_`. Strings are enclosed in
+//! double quotes and support the escapes `\"`, `\\`, `\n` and `\t`. An operand
+//! without a comparison is only allowed for the literals `true` and `false`.
+//! Addresses are IPv4 or IPv6 addresses with an optional prefix length, e.g.
+//! `10.0.0.0/8` or `2001:db8::/32`, and denote `Value::Cidr` blocks; an address
+//! without a prefix length is a block of one, which equals the address it names.
+//!
+//! # Printing
+//!
+//! `Condition` implements `Display` producing this syntax. Printing a parsed
+//! condition and parsing the result produces an equal condition. Conditions
+//! built by hand round-trip as long as every `And` and `Or` has at least two
+//! constituents, attribute names follow the syntax above, and numbers are finite.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::condition::*;
+use super::network::Cidr;
+
+/// Byte range of the source text a token or error refers to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+}
+
+/// Error produced when condition text cannot be parsed.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseConditionError {
+    /// Location of the error in the source text
+    pub span: Span,
+    /// Description of the error
+    pub message: String,
+}
+
+impl ParseConditionError {
+    fn new<M: Into<String>>(span: Span, message: M) -> Self {
+        ParseConditionError {
+            span,
+            message: message.into(),
+        }
+    }
+
+    /// Render the error with the offending source line and a marker under the
+    /// erroneous text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use authorization_core::condition::*;
+    ///
+    /// let source = "a == 1 && && b";
+    /// let error = source.parse::<Condition>().unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     error.render(source),
+    ///     "a == 1 && && b\n          ^^\nexpected operand, found `&&`"
+    /// );
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let line_start = source[..self.span.start.min(source.len())]
+            .rfind('\n')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let line_end = source[line_start..]
+            .find('\n')
+            .map(|i| line_start + i)
+            .unwrap_or(source.len());
+        let line = &source[line_start..line_end];
+        let indent = source[line_start..self.span.start].chars().count();
+        let width = source[self.span.start..self.span.end.min(line_end)]
+            .chars()
+            .count()
+            .max(1);
+        format!(
+            "{}\n{}{}\n{}",
+            line,
+            " ".repeat(indent),
+            "^".repeat(width),
+            self.message
+        )
+    }
+}
+
+impl fmt::Display for ParseConditionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for ParseConditionError {}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Addr(Cidr),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    AndAnd,
+    OrOr,
+    Bang,
+    Cmp(Comparison),
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Token::*;
+        match self {
+            Ident(s) => write!(f, "`{}`", s),
+            Str(s) => write!(f, "string {:?}", s),
+            Num(n) => write!(f, "number {}", n),
+            Addr(a) => write!(f, "address {}", a),
+            LParen => write!(f, "`(`"),
+            RParen => write!(f, "`)`"),
+            LBracket => write!(f, "`[`"),
+            RBracket => write!(f, "`]`"),
+            Comma => write!(f, "`,`"),
+            AndAnd => write!(f, "`&&`"),
+            OrOr => write!(f, "`||`"),
+            Bang => write!(f, "`!`"),
+            Cmp(c) => write!(f, "`{}`", comparison_symbol(*c)),
+            End => write!(f, "end of input"),
+        }
+    }
+}
+
+/// Private helper. Split source text into tokens with their spans. The last
+/// token is always `Token::End`.
+fn tokenize(source: &str) -> Result<Vec<(Token, Span)>, ParseConditionError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let mut next_is = |expected: char| {
+            if chars.peek().map(|(_, c)| *c) == Some(expected) {
+                chars.next();
+                true
+            } else {
+                false
+            }
+        };
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            ',' => Token::Comma,
+            '&' if next_is('&') => Token::AndAnd,
+            '|' if next_is('|') => Token::OrOr,
+            '=' if next_is('=') => Token::Cmp(Comparison::Eq),
+            '!' if next_is('=') => Token::Cmp(Comparison::Ne),
+            '!' => Token::Bang,
+            '<' if next_is('=') => Token::Cmp(Comparison::Le),
+            '<' => Token::Cmp(Comparison::Lt),
+            '>' if next_is('=') => Token::Cmp(Comparison::Ge),
+            '>' => Token::Cmp(Comparison::Gt),
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        None => {
+                            return Err(ParseConditionError::new(
+                                Span::new(start, source.len()),
+                                "unterminated string",
+                            ))
+                        }
+                        Some((_, '"')) => break,
+                        Some((at, '\\')) => match chars.next() {
+                            Some((_, '"')) => s.push('"'),
+                            Some((_, '\\')) => s.push('\\'),
+                            Some((_, 'n')) => s.push('\n'),
+                            Some((_, 't')) => s.push('\t'),
+                            Some((end, c)) => {
+                                return Err(ParseConditionError::new(
+                                    Span::new(at, end + c.len_utf8()),
+                                    format!("unknown escape `\\{}`", c),
+                                ))
+                            }
+                            None => {
+                                return Err(ParseConditionError::new(
+                                    Span::new(start, source.len()),
+                                    "unterminated string",
+                                ))
+                            }
+                        },
+                        Some((_, c)) => s.push(c),
+                    }
+                }
+                Token::Str(s)
+            }
+            c if is_address(&source[start..]) => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) = chars.peek() {
+                    if c.is_ascii_alphanumeric() || matches!(c, '.' | ':' | '/') {
+                        end = i + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let text = &source[start..end];
+                match text.parse() {
+                    Ok(a) => Token::Addr(a),
+                    Err(_) => {
+                        return Err(ParseConditionError::new(
+                            Span::new(start, end),
+                            format!("invalid address `{}`", text),
+                        ))
+                    }
+                }
+            }
+            c if c == '-' || c.is_ascii_digit() => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) = chars.peek() {
+                    if c.is_ascii_digit() || *c == '.' {
+                        end = i + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let text = &source[start..end];
+                let valid = text
+                    .trim_start_matches('-')
+                    .split('.')
+                    .all(|part| !part.is_empty())
+                    && text.matches('.').count() <= 1;
+                match text.parse() {
+                    Ok(n) if valid => Token::Num(n),
+                    _ => {
+                        return Err(ParseConditionError::new(
+                            Span::new(start, end),
+                            format!("invalid number `{}`", text),
+                        ))
+                    }
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) = chars.peek() {
+                    if c.is_alphanumeric() || *c == '_' || *c == '.' {
+                        end = i + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let text = &source[start..end];
+                if !is_attribute_name(text) {
+                    return Err(ParseConditionError::new(
+                        Span::new(start, end),
+                        format!("invalid attribute name `{}`", text),
+                    ));
+                }
+                Token::Ident(text.into())
+            }
+            c => {
+                return Err(ParseConditionError::new(
+                    Span::new(start, start + c.len_utf8()),
+                    format!("unexpected character `{}`", c),
+                ))
+            }
+        };
+        let end = chars.peek().map(|(i, _)| *i).unwrap_or(source.len());
+        tokens.push((token, Span::new(start, end)));
+    }
+    tokens.push((Token::End, Span::new(source.len(), source.len())));
+    Ok(tokens)
+}
+
+/// Private helper. Determine if text starts with an address rather than a
+/// number or attribute name. The characters up to the first that can't be part
+/// of an address must contain a `:`, as only IPv6 addresses do, or start with a
+/// digit and contain a `/` or three `.`s.
+fn is_address(text: &str) -> bool {
+    let text = text
+        .split(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | ':' | '/')))
+        .next()
+        .unwrap_or("");
+    text.contains(':')
+        || text.starts_with(|c: char| c.is_ascii_digit())
+            && (text.contains('/') || text.matches('.').count() == 3)
+}
+
+/// Private helper. Determine if a name is a valid attribute name.
+fn is_attribute_name(name: &str) -> bool {
+    name.split('.').all(|part| {
+        let mut chars = part.chars();
+        matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+            && chars.all(|c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+/// Private helper. Determine if a name is reserved by the syntax.
+fn is_keyword(name: &str) -> bool {
+    matches!(name, "true" | "false" | "in" | "exists")
+}
+
+/// Recursive descent parser over a token stream.
+struct Parser {
+    tokens: Vec<(Token, Span)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn span(&self) -> Span {
+        self.tokens[self.pos].1
+    }
+
+    fn advance(&mut self) -> (Token, Span) {
+        let token = self.tokens[self.pos].clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseConditionError {
+        ParseConditionError::new(
+            self.span(),
+            format!("expected {}, found {}", expected, self.peek()),
+        )
+    }
+
+    fn expect(&mut self, token: Token, expected: &str) -> Result<Span, ParseConditionError> {
+        if *self.peek() == token {
+            Ok(self.advance().1)
+        } else {
+            Err(self.unexpected(expected))
+        }
+    }
+
+    fn condition(&mut self) -> Result<Condition, ParseConditionError> {
+        let mut terms = vec![self.and()?];
+        while *self.peek() == Token::OrOr {
+            self.advance();
+            terms.push(self.and()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Condition::Or(terms)
+        })
+    }
+
+    fn and(&mut self) -> Result<Condition, ParseConditionError> {
+        let mut terms = vec![self.unary()?];
+        while *self.peek() == Token::AndAnd {
+            self.advance();
+            terms.push(self.unary()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Condition::And(terms)
+        })
+    }
+
+    fn unary(&mut self) -> Result<Condition, ParseConditionError> {
+        match self.peek() {
+            Token::Bang => {
+                self.advance();
+                Ok(Condition::Not(Box::new(self.unary()?)))
+            }
+            Token::LParen => {
+                self.advance();
+                let condition = self.condition()?;
+                self.expect(Token::RParen, "`)`")?;
+                Ok(condition)
+            }
+            Token::Ident(name) if name == "exists" => {
+                self.advance();
+                self.expect(Token::LParen, "`(` after `exists`")?;
+                let name = match self.advance() {
+                    (Token::Ident(name), _) if !is_keyword(&name) => name,
+                    (token, span) => {
+                        return Err(ParseConditionError::new(
+                            span,
+                            format!("expected attribute, found {}", token),
+                        ))
+                    }
+                };
+                self.expect(Token::RParen, "`)`")?;
+                Ok(Condition::Exists(name))
+            }
+            _ => self.comparison(),
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Condition, ParseConditionError> {
+        let (left, left_span) = self.operand()?;
+        match self.peek().clone() {
+            Token::Cmp(op) => {
+                self.advance();
+                let (right, _) = self.operand()?;
+                Ok(Condition::Compare(op, left, right))
+            }
+            Token::Ident(kw) if kw == "in" => {
+                self.advance();
+                let (right, _) = self.operand()?;
+                Ok(Condition::In(left, right))
+            }
+            _ => match left {
+                Operand::Const(Value::Bool(b)) => Ok(Condition::Const(b)),
+                _ => Err(ParseConditionError::new(
+                    left_span,
+                    format!("expected comparison after operand, found {}", self.peek()),
+                )),
+            },
+        }
+    }
+
+    fn operand(&mut self) -> Result<(Operand, Span), ParseConditionError> {
+        match self.peek().clone() {
+            Token::Ident(name) if !is_keyword(&name) => {
+                let (_, span) = self.advance();
+                Ok((Operand::Attr(name), span))
+            }
+            Token::Ident(name) if name == "in" || name == "exists" => {
+                Err(self.unexpected("operand"))
+            }
+            _ => {
+                let start = self.span().start;
+                let value = self.literal()?;
+                let end = self.tokens[self.pos.saturating_sub(1)].1.end;
+                Ok((Operand::Const(value), Span::new(start, end)))
+            }
+        }
+    }
+
+    fn literal(&mut self) -> Result<Value, ParseConditionError> {
+        match self.peek().clone() {
+            Token::Ident(name) if name == "true" => {
+                self.advance();
+                Ok(Value::Bool(true))
+            }
+            Token::Ident(name) if name == "false" => {
+                self.advance();
+                Ok(Value::Bool(false))
+            }
+            Token::Num(n) => {
+                self.advance();
+                Ok(Value::Num(n))
+            }
+            Token::Str(s) => {
+                self.advance();
+                Ok(Value::Str(s))
+            }
+            Token::Addr(a) => {
+                self.advance();
+                Ok(Value::Cidr(a))
+            }
+            Token::LBracket => {
+                self.advance();
+                let mut values = Vec::new();
+                if *self.peek() != Token::RBracket {
+                    values.push(self.literal()?);
+                    while *self.peek() == Token::Comma {
+                        self.advance();
+                        values.push(self.literal()?);
+                    }
+                }
+                self.expect(Token::RBracket, "`,` or `]`")?;
+                Ok(Value::Set(values))
+            }
+            _ => Err(self.unexpected("operand")),
+        }
+    }
+}
+
+/// Parse a condition from text.
+///
+/// # Examples
+///
+/// ```
+/// use authorization_core::condition::*;
+///
+/// let condition: Condition = "resource.owner == principal.id && !(request.size > 10)"
+///     .parse()
+///     .unwrap();
+///
+/// assert_eq!(
+///     condition.to_string(),
+///     "resource.owner == principal.id && !(request.size > 10)"
+/// );
+/// ```
+impl FromStr for Condition {
+    type Err = ParseConditionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let condition = parser.condition()?;
+        match parser.peek() {
+            Token::End => Ok(condition),
+            _ => Err(parser.unexpected("`&&`, `||` or end of input")),
+        }
+    }
+}
+
+/// Private helper. Symbol used for a comparison in the syntax.
+fn comparison_symbol(op: Comparison) -> &'static str {
+    use Comparison::*;
+    match op {
+        Eq => "==",
+        Ne => "!=",
+        Lt => "<",
+        Le => "<=",
+        Gt => ">",
+        Ge => ">=",
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Num(n) => write!(f, "{}", n),
+            Value::Str(s) => {
+                write!(f, "\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => write!(f, "\\\"")?,
+                        '\\' => write!(f, "\\\\")?,
+                        '\n' => write!(f, "\\n")?,
+                        '\t' => write!(f, "\\t")?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Value::Set(values) => {
+                write!(f, "[")?;
+                for (i, v) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                write!(f, "]")
+            }
+            Value::Cidr(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Attr(name) => write!(f, "{}", name),
+            Operand::Const(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// Private helper. Write a constituent of a compound condition, parenthesized
+/// if it is itself compound so that the structure is preserved.
+fn write_constituent(f: &mut fmt::Formatter<'_>, c: &Condition) -> fmt::Result {
+    match c {
+        Condition::And(cs) | Condition::Or(cs) if cs.len() > 1 => write!(f, "({})", c),
+        c => write!(f, "{}", c),
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Condition::*;
+        match self {
+            Const(b) => write!(f, "{}", b),
+            And(cs) | Or(cs) if cs.len() == 1 => write!(f, "{}", cs[0]),
+            And(cs) if cs.is_empty() => write!(f, "true"),
+            Or(cs) if cs.is_empty() => write!(f, "false"),
+            And(cs) | Or(cs) => {
+                let separator = if matches!(self, And(_)) {
+                    " && "
+                } else {
+                    " || "
+                };
+                for (i, c) in cs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{}", separator)?;
+                    }
+                    write_constituent(f, c)?;
+                }
+                Ok(())
+            }
+            Not(c) => match **c {
+                Const(_) | Not(_) | Exists(_) => write!(f, "!{}", c),
+                _ => write!(f, "!({})", c),
+            },
+            Compare(op, l, r) => write!(f, "{} {} {}", l, comparison_symbol(*op), r),
+            In(l, r) => write!(f, "{} in {}", l, r),
+            Exists(name) => write!(f, "exists({})", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn parse(s: &str) -> Condition {
+        s.parse().unwrap()
+    }
+
+    fn attr(name: &str) -> Operand {
+        Operand::attr(name)
+    }
+
+    fn val<V: Into<Value>>(v: V) -> Operand {
+        Operand::value(v)
+    }
+
+    fn error(s: &str) -> ParseConditionError {
+        s.parse::<Condition>().unwrap_err()
+    }
+
+    #[test]
+    fn test_parse_comparisons() {
+        use Comparison::*;
+
+        assert_eq!(
+            parse("resource.owner == principal.id"),
+            Condition::Compare(Eq, attr("resource.owner"), attr("principal.id"))
+        );
+        assert_eq!(
+            parse("a != \"x\""),
+            Condition::Compare(Ne, attr("a"), val("x"))
+        );
+        assert_eq!(parse("a < 1"), Condition::Compare(Lt, attr("a"), val(1)));
+        assert_eq!(
+            parse("a <= -1.5"),
+            Condition::Compare(Le, attr("a"), val(-1.5))
+        );
+        assert_eq!(parse("a > b"), Condition::Compare(Gt, attr("a"), attr("b")));
+        assert_eq!(
+            parse("a >= true"),
+            Condition::Compare(Ge, attr("a"), val(true))
+        );
+    }
+
+    #[test]
+    fn test_parse_in_and_sets() {
+        assert_eq!(
+            parse("principal.group in [\"dev\", \"ops\"]"),
+            Condition::In(attr("principal.group"), val(vec!["dev", "ops"]))
+        );
+        assert_eq!(
+            parse("\"dev\" in principal.groups"),
+            Condition::In(val("dev"), attr("principal.groups"))
+        );
+        assert_eq!(
+            parse("a in []"),
+            Condition::In(attr("a"), Operand::Const(Value::Set(vec![])))
+        );
+    }
+
+    #[test]
+    fn test_parse_addresses() {
+        let block = |s: &str| val(s.parse::<Cidr>().unwrap());
+
+        assert_eq!(
+            parse("request.ip in 10.0.0.0/8"),
+            Condition::In(attr("request.ip"), block("10.0.0.0/8"))
+        );
+        assert_eq!(
+            parse("request.ip in [192.168.1.1, 2001:db8::/32, fd00::/8, ::1]"),
+            Condition::In(
+                attr("request.ip"),
+                Operand::Const(Value::Set(vec![
+                    Value::Cidr("192.168.1.1".parse().unwrap()),
+                    Value::Cidr("2001:db8::/32".parse().unwrap()),
+                    Value::Cidr("fd00::/8".parse().unwrap()),
+                    Value::Cidr("::1".parse().unwrap()),
+                ]))
+            )
+        );
+        assert_eq!(
+            parse("a == 1.5"),
+            Condition::Compare(Comparison::Eq, attr("a"), val(1.5))
+        );
+    }
+
+    #[test]
+    fn test_parse_and_evaluate_address_condition() {
+        let condition = parse("request.ip in 10.0.0.0/8 && resource.owner == principal.id");
+        let env = |ip: &str| {
+            AttributeEnvironment::new()
+                .with("request.ip", ip)
+                .with("resource.owner", "alice")
+                .with("principal.id", "alice")
+        };
+
+        assert_eq!(env("10.1.2.3").evaluate(&condition), Ok(true));
+        assert_eq!(env("192.168.1.1").evaluate(&condition), Ok(false));
+        assert_eq!(
+            env("10.1.2.3")
+                .with("principal.id", "bob")
+                .evaluate(&condition),
+            Ok(false)
+        );
+
+        let env = AttributeEnvironment::new().with("request.ip", "10.0.0.1");
+        assert_eq!(env.evaluate(&parse("request.ip == 10.0.0.1")), Ok(true));
+        assert_eq!(env.evaluate(&parse("request.ip != 10.0.0.1")), Ok(false));
+        assert_eq!(env.evaluate(&parse("request.ip != 10.0.0.2")), Ok(true));
+    }
+
+    #[test]
+    fn test_parse_exists_and_constants() {
+        assert_eq!(parse("exists(a.b)"), Condition::Exists("a.b".into()));
+        assert_eq!(parse("true"), Condition::Const(true));
+        assert_eq!(parse("false"), Condition::Const(false));
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        use Condition::*;
+        let a = || parse("a == 1");
+        let b = || parse("b == 1");
+        let c = || parse("c == 1");
+
+        assert_eq!(
+            parse("a == 1 || b == 1 && c == 1"),
+            Or(vec![a(), And(vec![b(), c()])])
+        );
+        assert_eq!(
+            parse("(a == 1 || b == 1) && c == 1"),
+            And(vec![Or(vec![a(), b()]), c()])
+        );
+        assert_eq!(
+            parse("!a == 1 && !!b == 1"),
+            And(vec![Not(Box::new(a())), Not(Box::new(Not(Box::new(b()))))])
+        );
+        assert_eq!(
+            parse("a == 1 && b == 1 && c == 1"),
+            And(vec![a(), b(), c()])
+        );
+        assert_eq!(
+            parse("(a == 1 && b == 1) && c == 1"),
+            And(vec![And(vec![a(), b()]), c()])
+        );
+    }
+
+    #[test]
+    fn test_parse_string_escapes() {
+        assert_eq!(
+            parse(r#"a == "q\"b\\n\n\t""#),
+            Condition::Compare(Comparison::Eq, attr("a"), val("q\"b\\n\n\t"))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            error("a == "),
+            ParseConditionError::new(Span::new(5, 5), "expected operand, found end of input")
+        );
+        assert_eq!(
+            error("a == 1 b"),
+            ParseConditionError::new(
+                Span::new(7, 8),
+                "expected `&&`, `||` or end of input, found `b`"
+            )
+        );
+        assert_eq!(
+            error("resource.owner && b == 1"),
+            ParseConditionError::new(
+                Span::new(0, 14),
+                "expected comparison after operand, found `&&`"
+            )
+        );
+        assert_eq!(
+            error("(a == 1"),
+            ParseConditionError::new(Span::new(7, 7), "expected `)`, found end of input")
+        );
+        assert_eq!(
+            error("a == \"abc"),
+            ParseConditionError::new(Span::new(5, 9), "unterminated string")
+        );
+        assert_eq!(
+            error("a == \"\\q\""),
+            ParseConditionError::new(Span::new(6, 8), "unknown escape `\\q`")
+        );
+        assert_eq!(
+            error("a == 1.2.3"),
+            ParseConditionError::new(Span::new(5, 10), "invalid number `1.2.3`")
+        );
+        assert_eq!(
+            error("a in 10.0.0/8"),
+            ParseConditionError::new(Span::new(5, 13), "invalid address `10.0.0/8`")
+        );
+        assert_eq!(
+            error("a in 10.0.0.0/33"),
+            ParseConditionError::new(Span::new(5, 16), "invalid address `10.0.0.0/33`")
+        );
+        assert_eq!(
+            error("a.b. == 1"),
+            ParseConditionError::new(Span::new(0, 4), "invalid attribute name `a.b.`")
+        );
+        assert_eq!(
+            error("a == 1 & b"),
+            ParseConditionError::new(Span::new(7, 8), "unexpected character `&`")
+        );
+        assert_eq!(
+            error("a in [1, ]"),
+            ParseConditionError::new(Span::new(9, 10), "expected operand, found `]`")
+        );
+        assert_eq!(
+            error("exists(true)"),
+            ParseConditionError::new(Span::new(7, 11), "expected attribute, found `true`")
+        );
+    }
+
+    #[test]
+    fn test_error_display() {
+        let e = error("a == ");
+
+        assert_eq!(
+            e.to_string(),
+            "expected operand, found end of input at 5..5"
+        );
+        assert_eq!(
+            e.render("a == "),
+            "a == \n     ^\nexpected operand, found end of input"
+        );
+    }
+
+    #[test]
+    fn test_render_multiline() {
+        let source = "a == 1 &&\n  b ==";
+
+        assert_eq!(
+            error(source).render(source),
+            "  b ==\n      ^\nexpected operand, found end of input"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let sources = [
+            "resource.owner == principal.id",
+            "a == 1 || b == 2 && c == 3",
+            "(a == 1 || b == 2) && c == 3",
+            "(a == 1 && b == 2) && c == 3",
+            "a == 1 && (b == 2 && c == 3)",
+            "!(a == 1 || b == 2)",
+            "!!a == 1",
+            "!exists(x) || x in [1, \"two\", false, [3]]",
+            "a == \"quote\\\" backslash\\\\ newline\\n\"",
+            "true && false",
+            "a >= -0.25 && b != 100",
+            "request.ip in 10.0.0.0/8 && resource.owner == principal.id",
+            "ip in [10.0.0.1, ::ffff:192.168.0.0/112, fe80::/10]",
+        ];
+        for source in &sources {
+            let condition = parse(source);
+            let printed = condition.to_string();
+            assert_eq!(
+                parse(&printed),
+                condition,
+                "{} printed as {}",
+                source,
+                printed
+            );
+        }
+    }
+
+    #[test]
+    fn test_print() {
+        assert_eq!(
+            parse("(a==1||b==2)&&!(c in [1,2])").to_string(),
+            "(a == 1 || b == 2) && !(c in [1, 2])"
+        );
+        assert_eq!(Condition::And(vec![]).to_string(), "true");
+        assert_eq!(Condition::Or(vec![]).to_string(), "false");
+        assert_eq!(
+            Condition::And(vec![Condition::Exists("a".into())]).to_string(),
+            "exists(a)"
+        );
+    }
+}
@@ -2,6 +2,7 @@
 
 use super::effect::*;
 use super::environment::*;
+use super::obligation::Duties;
 
 ///  A dependent authorization effect. A dependent effect is evaluated in the context of
 /// an environment to produce a `authorization_core::effect::ComputedEffect`.
@@ -23,6 +24,18 @@ pub enum DependentEffect<CExp> {
     /// Combines the effects of multiple principals. It is evaluated using
     /// `authorization_core::effect::combine_strict(_)`
     Disjoint(Vec<DependentEffect<CExp>>),
+    /// Combines multiple effects, in order, using a chosen combining algorithm.
+    Combined(Algorithm, Vec<DependentEffect<CExp>>),
+    /// Selects the effect of the highest-priority constituents that are not
+    /// silent, whatever their effect. It is evaluated using
+    /// `authorization_core::effect::combine_prioritized(_)`, so `DENY` breaks ties.
+    Prioritized(Vec<(Priority, DependentEffect<CExp>)>),
+    /// Attaches obligations and advice to an effect. It resolves to the wrapped
+    /// effect and contributes its duties if the effect decides the result.
+    Obligated(Duties, Box<DependentEffect<CExp>>),
+    /// Identifies the policy statement producing an effect. It resolves to the
+    /// wrapped effect.
+    Identified(String, Box<DependentEffect<CExp>>),
 }
 
 impl<CExp> DependentEffect<CExp> {
@@ -52,12 +65,298 @@ impl<CExp> DependentEffect<CExp> {
             }
             Disjoint(effs) => {
                 let resolved: Result<Vec<ComputedEffect>, Env::Err> =
-                    effs.into_iter().map(|p| p.resolve(environment)).collect();
+                    effs.iter().map(|p| p.resolve(environment)).collect();
                 let resolved = resolved?;
                 let resolved = combine_strict(resolved);
 
                 Ok(resolved)
             }
+            Combined(algorithm, effs) => {
+                let resolved: Result<Vec<ComputedEffect>, Env::Err> =
+                    effs.iter().map(|p| p.resolve(environment)).collect();
+                Ok(algorithm.combine(&resolved?))
+            }
+            Prioritized(effs) => {
+                let resolved: Result<Vec<(Priority, ComputedEffect)>, Env::Err> = effs
+                    .iter()
+                    .map(|(priority, p)| Ok((*priority, p.resolve(environment)?)))
+                    .collect();
+                Ok(combine_prioritized(resolved?))
+            }
+            Obligated(_, eff) | Identified(_, eff) => eff.resolve(environment),
+        }
+    }
+
+    /// Determine if this node or any node below it satisfies a predicate.
+    pub fn any_node<P>(&self, predicate: P) -> bool
+    where
+        P: Fn(&DependentEffect<CExp>) -> bool,
+    {
+        self.any_node_dyn(&predicate)
+    }
+
+    /// Private helper. Search the nodes depth first.
+    fn any_node_dyn(&self, predicate: &dyn Fn(&DependentEffect<CExp>) -> bool) -> bool {
+        use DependentEffect::*;
+        predicate(self)
+            || match self {
+                Silent | Fixed(_) | Atomic(_, _) => false,
+                Aggregate(effs) | Disjoint(effs) | Combined(_, effs) => {
+                    effs.iter().any(|e| e.any_node_dyn(predicate))
+                }
+                Prioritized(effs) => effs.iter().any(|(_, e)| e.any_node_dyn(predicate)),
+                Obligated(_, eff) | Identified(_, eff) => eff.any_node_dyn(predicate),
+            }
+    }
+}
+
+/// Outcome of a short-circuiting resolution.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Resolution {
+    /// The resolved effect.
+    pub effect: ComputedEffect,
+    /// The number of conditions tested in the environment.
+    pub evaluated: usize,
+}
+
+impl<CExp> DependentEffect<CExp> {
+    /// Evaluate dependent effect in an envionmental context, testing no more
+    /// conditions than needed. Each combination stops once the constituents
+    /// resolved so far decide it, e.g. an `Aggregate` stops at the first `DENY`
+    /// and a `Disjoint` at the first `SILENT`.
+    ///
+    /// The effect is the same as for `resolve` except that conditions that are
+    /// not tested cannot produce errors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use authorization_core::dependent_effect::*;
+    /// use authorization_core::effect::*;
+    /// use authorization_core::environment::*;
+    ///
+    /// let effect = DependentEffect::Aggregate(vec![
+    ///     DependentEffect::Atomic(Effect::DENY, ()),
+    ///     DependentEffect::Atomic(Effect::ALLOW, ()),
+    /// ]);
+    ///
+    /// let actual = effect.resolve_short_circuit(&PositiveEnvironment);
+    ///
+    /// assert_eq!(actual, Ok(Resolution { effect: DENY, evaluated: 1 }));
+    /// ```
+    pub fn resolve_short_circuit<Env>(&self, environment: &Env) -> Result<Resolution, Env::Err>
+    where
+        Env: Environment<CExp = CExp>,
+    {
+        let mut evaluated = 0;
+        let effect = self.resolve_counting(environment, None, &mut evaluated)?;
+        Ok(Resolution { effect, evaluated })
+    }
+
+    /// Like `resolve_short_circuit` but resolves the constituents of each
+    /// `Aggregate` and `Disjoint` in order of ascending cost, so fixed effects
+    /// and cheap conditions can decide the outcome before expensive conditions
+    /// are tested. The cost of a constituent is the total cost of its conditions
+    /// and constituents of equal cost keep their declared order. Constituents of
+    /// a `Combined` are not reordered since algorithms such as first-applicable
+    /// depend on their order, and constituents of a `Prioritized` are only
+    /// reordered among those of equal priority.
+    ///
+    /// Errors can differ from `resolve` as conditions are tested in a different order.
+    pub fn resolve_short_circuit_by_cost<Env, F>(
+        &self,
+        environment: &Env,
+        cost: F,
+    ) -> Result<Resolution, Env::Err>
+    where
+        Env: Environment<CExp = CExp>,
+        F: Fn(&CExp) -> u64,
+    {
+        let mut evaluated = 0;
+        let effect = self.resolve_counting(environment, Some(&cost), &mut evaluated)?;
+        Ok(Resolution { effect, evaluated })
+    }
+
+    /// Private helper. Short-circuiting resolution counting tested conditions.
+    fn resolve_counting<Env>(
+        &self,
+        environment: &Env,
+        cost: Option<&dyn Fn(&CExp) -> u64>,
+        evaluated: &mut usize,
+    ) -> Result<ComputedEffect, Env::Err>
+    where
+        Env: Environment<CExp = CExp>,
+    {
+        use DependentEffect::*;
+
+        match self {
+            Silent => Ok(SILENT),
+            Fixed(eff) => Ok(Some(*eff).into()),
+            Atomic(eff, cexp) => {
+                *evaluated += 1;
+                if environment.test_condition(cexp)? {
+                    Ok(Some(*eff).into())
+                } else {
+                    Ok(SILENT)
+                }
+            }
+            Aggregate(effs) => Self::resolve_until(effs, &NonStrict, cost, environment, evaluated),
+            Disjoint(effs) => Self::resolve_until(effs, &Strict, cost, environment, evaluated),
+            Combined(algorithm, effs) => {
+                Self::resolve_until(effs, algorithm, None, environment, evaluated)
+            }
+            Prioritized(effs) => {
+                for group in priority_groups(effs.iter().map(|(p, _)| *p)) {
+                    let group = group.into_iter().map(|i| &effs[i].1);
+                    let resolved =
+                        Self::resolve_until(group, &NonStrict, cost, environment, evaluated)?;
+                    if resolved != SILENT {
+                        return Ok(resolved);
+                    }
+                }
+                Ok(SILENT)
+            }
+            Obligated(_, eff) | Identified(_, eff) => {
+                eff.resolve_counting(environment, cost, evaluated)
+            }
+        }
+    }
+
+    /// Private helper. Resolve constituents, in order of cost if given, until
+    /// those resolved decide the combination.
+    fn resolve_until<'e, Env>(
+        effs: impl IntoIterator<Item = &'e DependentEffect<CExp>>,
+        algorithm: &dyn CombiningAlgorithm,
+        order_by: Option<&dyn Fn(&CExp) -> u64>,
+        environment: &Env,
+        evaluated: &mut usize,
+    ) -> Result<ComputedEffect, Env::Err>
+    where
+        Env: Environment<CExp = CExp>,
+        CExp: 'e,
+    {
+        let mut ordered: Vec<&DependentEffect<CExp>> = effs.into_iter().collect();
+        if let Some(cost) = order_by {
+            ordered.sort_by_cached_key(|e| e.cost(cost));
+        }
+        let mut resolved = Vec::with_capacity(ordered.len());
+        for eff in ordered {
+            resolved.push(eff.resolve_counting(environment, order_by, evaluated)?);
+            if let Some(decided) = algorithm.decided(&resolved) {
+                return Ok(decided);
+            }
+        }
+        Ok(algorithm.combine(&resolved))
+    }
+
+    /// Private helper. Total cost of the conditions in an effect.
+    fn cost(&self, cost: &dyn Fn(&CExp) -> u64) -> u64 {
+        use DependentEffect::*;
+        match self {
+            Silent | Fixed(_) => 0,
+            Atomic(_, cexp) => cost(cexp),
+            Aggregate(effs) | Disjoint(effs) | Combined(_, effs) => effs
+                .iter()
+                .fold(0, |total, e| total.saturating_add(e.cost(cost))),
+            Prioritized(effs) => effs
+                .iter()
+                .fold(0, |total, (_, e)| total.saturating_add(e.cost(cost))),
+            Obligated(_, eff) | Identified(_, eff) => eff.cost(cost),
+        }
+    }
+}
+
+/// How resolution treats errors from testing conditions.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ErrorPolicy {
+    /// Abort resolution with the error, as `resolve` does.
+    #[default]
+    FailFast,
+    /// Resolve a failed condition to `DENY` whatever effect it guards.
+    ErrorAsDeny,
+    /// Resolve a failed condition to `SILENT` as if it did not hold.
+    ErrorAsSilent,
+    /// Resolve a failed condition to an indeterminate outcome that could be
+    /// the guarded effect or silence, and combine it with the other outcomes.
+    Indeterminate,
+}
+
+impl<CExp> DependentEffect<CExp> {
+    /// Evaluate dependent effect in an envionmental context, handling errors
+    /// according to a policy. Only `ErrorPolicy::FailFast` produces `Err(_)` and
+    /// only `ErrorPolicy::Indeterminate` produces indeterminate outcomes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use authorization_core::dependent_effect::*;
+    /// use authorization_core::effect::*;
+    /// use authorization_core::environment::*;
+    ///
+    /// let env = FailingEnvironment::new("backend unavailable");
+    ///
+    /// // a fixed DENY decides whether or not the condition could be tested
+    /// let effect = DependentEffect::Aggregate(vec![
+    ///     DependentEffect::Fixed(Effect::DENY),
+    ///     DependentEffect::Atomic(Effect::ALLOW, ()),
+    /// ]);
+    /// assert_eq!(effect.resolve(&env), Err("backend unavailable"));
+    /// assert_eq!(
+    ///     effect.resolve_with_policy(&env, ErrorPolicy::Indeterminate),
+    ///     Ok(DENY.into())
+    /// );
+    /// ```
+    pub fn resolve_with_policy<Env>(
+        &self,
+        environment: &Env,
+        policy: ErrorPolicy,
+    ) -> Result<Outcome, Env::Err>
+    where
+        Env: Environment<CExp = CExp>,
+    {
+        use DependentEffect::*;
+        match self {
+            Silent => Ok(SILENT.into()),
+            Fixed(eff) => Ok(ComputedEffect::from(*eff).into()),
+            Atomic(eff, cexp) => match (environment.test_condition(cexp), policy) {
+                (Ok(true), _) => Ok(ComputedEffect::from(*eff).into()),
+                (Ok(false), _) => Ok(SILENT.into()),
+                (Err(err), ErrorPolicy::FailFast) => Err(err),
+                (Err(_), ErrorPolicy::ErrorAsDeny) => Ok(DENY.into()),
+                (Err(_), ErrorPolicy::ErrorAsSilent) => Ok(SILENT.into()),
+                (Err(_), ErrorPolicy::Indeterminate) => Ok(Outcome::indeterminate(*eff)),
+            },
+            Aggregate(effs) => {
+                let resolved: Result<Vec<Outcome>, Env::Err> = effs
+                    .iter()
+                    .map(|e| e.resolve_with_policy(environment, policy))
+                    .collect();
+                Ok(combine_outcomes_non_strict(resolved?))
+            }
+            Disjoint(effs) => {
+                let resolved: Result<Vec<Outcome>, Env::Err> = effs
+                    .iter()
+                    .map(|e| e.resolve_with_policy(environment, policy))
+                    .collect();
+                Ok(combine_outcomes_strict(resolved?))
+            }
+            Combined(algorithm, effs) => {
+                let resolved: Result<Vec<Outcome>, Env::Err> = effs
+                    .iter()
+                    .map(|e| e.resolve_with_policy(environment, policy))
+                    .collect();
+                Ok(combine_outcomes(algorithm, &resolved?))
+            }
+            Prioritized(effs) => {
+                let resolved: Result<Vec<(Priority, Outcome)>, Env::Err> = effs
+                    .iter()
+                    .map(|(priority, e)| {
+                        Ok((*priority, e.resolve_with_policy(environment, policy)?))
+                    })
+                    .collect();
+                Ok(combine_outcomes_prioritized(resolved?))
+            }
+            Obligated(_, eff) | Identified(_, eff) => eff.resolve_with_policy(environment, policy),
         }
     }
 }
@@ -73,6 +372,7 @@ where
 }
 
 #[cfg(test)]
+#[allow(clippy::unit_cmp, clippy::useless_vec)]
 mod tests {
 
     use super::*;
@@ -444,4 +744,392 @@ mod tests {
             Fixed(Effect::ALLOW),
         ]);
     }
+
+    #[test]
+    fn test_resolve_combined() {
+        use DependentEffect::*;
+        let effs = vec![
+            Atomic(Effect::ALLOW, 1u32),
+            Atomic(Effect::DENY, 2u32),
+            Fixed(Effect::ALLOW),
+        ];
+
+        let first = Combined(Algorithm::FirstApplicable, effs.clone());
+        assert_eq!(first.resolve(&1), Ok(ALLOW));
+        assert_eq!(first.resolve(&2), Ok(DENY));
+        assert_eq!(first.resolve(&3), Ok(ALLOW));
+
+        let permit = Combined(Algorithm::PermitOverrides, effs.clone());
+        assert_eq!(permit.resolve(&2), Ok(ALLOW));
+
+        let only_one = Combined(Algorithm::OnlyOneApplicable, effs.clone());
+        assert_eq!(only_one.resolve(&2), Ok(DENY));
+        assert_eq!(only_one.resolve(&3), Ok(ALLOW));
+
+        let deny_unless = Combined(
+            Algorithm::DenyUnlessPermit,
+            vec![Atomic(Effect::ALLOW, 1u32)],
+        );
+        assert_eq!(deny_unless.resolve(&1), Ok(ALLOW));
+        assert_eq!(deny_unless.resolve(&2), Ok(DENY));
+
+        let permit_unless = Combined(
+            Algorithm::PermitUnlessDeny,
+            vec![Atomic(Effect::DENY, 1u32)],
+        );
+        assert_eq!(permit_unless.resolve(&1), Ok(DENY));
+        assert_eq!(permit_unless.resolve(&2), Ok(ALLOW));
+
+        // the default algorithms agree with the built-in combinations
+        for value in 1..4 {
+            assert_eq!(
+                Combined(Algorithm::NonStrict, effs.clone()).resolve(&value),
+                Aggregate(effs.clone()).resolve(&value)
+            );
+            assert_eq!(
+                Combined(Algorithm::Strict, effs.clone()).resolve(&value),
+                Disjoint(effs.clone()).resolve(&value)
+            );
+        }
+    }
+
+    #[test]
+    fn test_short_circuit_combined() {
+        use DependentEffect::*;
+        let effect = Combined(
+            Algorithm::FirstApplicable,
+            vec![
+                Atomic(Effect::DENY, TestExpression::Miss),
+                Atomic(Effect::ALLOW, TestExpression::Match),
+                Atomic(Effect::DENY, TestExpression::Error),
+            ],
+        );
+
+        let actual = effect.resolve_short_circuit(&TestEnv);
+
+        assert_eq!(
+            actual,
+            Ok(Resolution {
+                effect: ALLOW,
+                evaluated: 2
+            })
+        );
+
+        // constituents of a combination are never reordered by cost
+        let actual = effect.resolve_short_circuit_by_cost(&TestEnv, |c| match c {
+            TestExpression::Error => 0,
+            _ => 1,
+        });
+        assert_eq!(actual.map(|r| r.effect), Ok(ALLOW));
+    }
+
+    #[test]
+    fn test_resolve_prioritized() {
+        use DependentEffect::*;
+        let effect = Prioritized(vec![
+            (1, Fixed(Effect::DENY)),
+            (3, Atomic(Effect::ALLOW, 1u32)),
+            (2, Atomic(Effect::DENY, 2u32)),
+            (3, Atomic(Effect::DENY, 3u32)),
+        ]);
+
+        // the highest-priority effect wins whatever it is
+        assert_eq!(effect.resolve(&1), Ok(ALLOW));
+        assert_eq!(effect.resolve(&2), Ok(DENY));
+        assert_eq!(effect.resolve(&4), Ok(DENY));
+
+        // ties are broken by DENY regardless of order
+        let tied =
+            |first, second| Prioritized(vec![(1, Fixed(first)), (1, Fixed(second))]).resolve(&0u32);
+        assert_eq!(tied(Effect::ALLOW, Effect::DENY), Ok(DENY));
+        assert_eq!(tied(Effect::DENY, Effect::ALLOW), Ok(DENY));
+
+        assert_eq!(Prioritized::<u32>(vec![]).resolve(&0), Ok(SILENT));
+        assert_eq!(
+            Prioritized(vec![(1, Atomic(Effect::ALLOW, 1u32))]).resolve(&0),
+            Ok(SILENT)
+        );
+    }
+
+    #[test]
+    fn test_prioritized_within_aggregate() {
+        use DependentEffect::*;
+        let emergency = Prioritized(vec![
+            (0, Fixed(Effect::DENY)),
+            (1, Atomic(Effect::ALLOW, 1u32)),
+        ]);
+        let effect = Aggregate(vec![emergency.clone(), Atomic(Effect::DENY, 2u32)]);
+
+        assert_eq!(effect.resolve(&1), Ok(ALLOW));
+        assert_eq!(effect.resolve(&2), Ok(DENY));
+
+        let effect = Aggregate(vec![emergency, Fixed(Effect::DENY)]);
+        assert_eq!(effect.resolve(&1), Ok(DENY));
+    }
+
+    #[test]
+    fn test_short_circuit_prioritized() {
+        use DependentEffect::*;
+        let effect = Prioritized(vec![
+            (1, Atomic(Effect::DENY, TestExpression::Error)),
+            (2, Atomic(Effect::ALLOW, TestExpression::Match)),
+            (3, Atomic(Effect::DENY, TestExpression::Miss)),
+            (2, Atomic(Effect::ALLOW, TestExpression::Match)),
+        ]);
+
+        let actual = effect.resolve_short_circuit(&TestEnv);
+
+        // lower priorities are not tested once a higher priority decides
+        assert_eq!(
+            actual,
+            Ok(Resolution {
+                effect: ALLOW,
+                evaluated: 3
+            })
+        );
+        assert_eq!(effect.resolve(&TestEnv), Err(()));
+        assert_eq!(
+            effect.resolve_with_policy(&TestEnv, ErrorPolicy::Indeterminate),
+            Ok(ALLOW.into())
+        );
+    }
+
+    #[test]
+    fn test_short_circuit_aggregate_stops_at_deny() {
+        use DependentEffect::*;
+        let effect = Aggregate(vec![
+            Atomic(Effect::ALLOW, TestExpression::Match),
+            Atomic(Effect::DENY, TestExpression::Match),
+            Atomic(Effect::ALLOW, TestExpression::Error),
+        ]);
+
+        let actual = effect.resolve_short_circuit(&TestEnv);
+
+        assert_eq!(
+            actual,
+            Ok(Resolution {
+                effect: DENY,
+                evaluated: 2
+            })
+        );
+        assert_eq!(effect.resolve(&TestEnv), Err(()));
+    }
+
+    #[test]
+    fn test_short_circuit_disjoint_stops_at_silence() {
+        use DependentEffect::*;
+        let effect = Disjoint(vec![
+            Aggregate(vec![
+                Atomic(Effect::ALLOW, TestExpression::Miss),
+                Atomic(Effect::DENY, TestExpression::Miss),
+            ]),
+            Atomic(Effect::ALLOW, TestExpression::Error),
+        ]);
+
+        let actual = effect.resolve_short_circuit(&TestEnv);
+
+        assert_eq!(
+            actual,
+            Ok(Resolution {
+                effect: SILENT,
+                evaluated: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_short_circuit_error() {
+        use DependentEffect::*;
+        let effect = Aggregate(vec![
+            Atomic(Effect::ALLOW, TestExpression::Error),
+            Fixed(Effect::DENY),
+        ]);
+
+        assert_eq!(effect.resolve_short_circuit(&TestEnv), Err(()));
+    }
+
+    #[test]
+    fn test_short_circuit_agrees_with_resolve() {
+        use DependentEffect::*;
+        use TestExpression::*;
+
+        let leaves = [
+            Silent,
+            Fixed(Effect::ALLOW),
+            Fixed(Effect::DENY),
+            Atomic(Effect::ALLOW, Match),
+            Atomic(Effect::ALLOW, Miss),
+            Atomic(Effect::DENY, Match),
+            Atomic(Effect::DENY, Miss),
+        ];
+        let mut pairs = Vec::new();
+        for a in &leaves {
+            for b in &leaves {
+                pairs.push(Aggregate(vec![a.clone(), b.clone()]));
+                pairs.push(Disjoint(vec![a.clone(), b.clone()]));
+            }
+        }
+        for a in &pairs {
+            for b in &leaves {
+                for effect in [
+                    Aggregate(vec![a.clone(), b.clone()]),
+                    Disjoint(vec![b.clone(), a.clone()]),
+                ] {
+                    let expected = effect.resolve(&TestEnv).unwrap();
+
+                    let actual = effect.resolve_short_circuit(&TestEnv).unwrap();
+                    assert_eq!(actual.effect, expected);
+
+                    let actual = effect
+                        .resolve_short_circuit_by_cost(&TestEnv, |_| 1)
+                        .unwrap();
+                    assert_eq!(actual.effect, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_short_circuit_by_cost() {
+        use DependentEffect::*;
+        let effect = Aggregate(vec![
+            Atomic(Effect::ALLOW, 1u32),
+            Atomic(Effect::ALLOW, 2u32),
+            Fixed(Effect::DENY),
+        ]);
+
+        let declared = effect.resolve_short_circuit(&1).unwrap();
+        assert_eq!(declared.evaluated, 2);
+
+        let actual = effect.resolve_short_circuit_by_cost(&1, |_| 1);
+        assert_eq!(
+            actual,
+            Ok(Resolution {
+                effect: DENY,
+                evaluated: 0
+            })
+        );
+
+        // the cheap miss decides before the expensive condition is tested
+        let effect = Disjoint(vec![
+            Atomic(Effect::ALLOW, 5u32),
+            Atomic(Effect::ALLOW, 3u32),
+        ]);
+        let actual = effect.resolve_short_circuit_by_cost(&5, |c| u64::from(*c));
+        assert_eq!(
+            actual,
+            Ok(Resolution {
+                effect: SILENT,
+                evaluated: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_error_policy_fail_fast() {
+        use DependentEffect::*;
+        let effect = Aggregate(vec![
+            Fixed(Effect::DENY),
+            Atomic(Effect::ALLOW, TestExpression::Error),
+        ]);
+
+        let actual = effect.resolve_with_policy(&TestEnv, ErrorPolicy::FailFast);
+
+        assert_eq!(actual, Err(()));
+    }
+
+    #[test]
+    fn test_error_policy_error_as_deny() {
+        use DependentEffect::*;
+        let effect = Aggregate(vec![
+            Fixed(Effect::ALLOW),
+            Atomic(Effect::ALLOW, TestExpression::Error),
+        ]);
+
+        let actual = effect.resolve_with_policy(&TestEnv, ErrorPolicy::ErrorAsDeny);
+
+        assert_eq!(actual, Ok(DENY.into()));
+    }
+
+    #[test]
+    fn test_error_policy_error_as_silent() {
+        use DependentEffect::*;
+        let effect = Aggregate(vec![
+            Fixed(Effect::ALLOW),
+            Atomic(Effect::DENY, TestExpression::Error),
+        ]);
+
+        let actual = effect.resolve_with_policy(&TestEnv, ErrorPolicy::ErrorAsSilent);
+
+        assert_eq!(actual, Ok(ALLOW.into()));
+    }
+
+    #[test]
+    fn test_error_policy_indeterminate() {
+        use DependentEffect::*;
+        use TestExpression::*;
+        let resolve = |effect: DependentEffect<TestExpression>| {
+            effect
+                .resolve_with_policy(&TestEnv, ErrorPolicy::Indeterminate)
+                .unwrap()
+        };
+
+        // decided regardless of the failed condition
+        assert_eq!(
+            resolve(Aggregate(vec![
+                Atomic(Effect::ALLOW, Error),
+                Fixed(Effect::DENY)
+            ])),
+            DENY.into()
+        );
+        assert_eq!(
+            resolve(Aggregate(vec![
+                Atomic(Effect::ALLOW, Error),
+                Fixed(Effect::ALLOW)
+            ])),
+            ALLOW.into()
+        );
+        assert_eq!(
+            resolve(Disjoint(vec![Atomic(Effect::ALLOW, Error), Silent])),
+            SILENT.into()
+        );
+
+        // not decided
+        let actual = resolve(Aggregate(vec![
+            Atomic(Effect::DENY, Error),
+            Fixed(Effect::ALLOW),
+        ]));
+        assert!(actual.is_indeterminate());
+        assert!(actual.could_be(ALLOW) && actual.could_be(DENY));
+        assert!(!actual.could_be(SILENT));
+
+        let actual = resolve(Disjoint(vec![
+            Atomic(Effect::ALLOW, Error),
+            Fixed(Effect::ALLOW),
+        ]));
+        assert_eq!(actual, Outcome::indeterminate(Effect::ALLOW));
+    }
+
+    #[test]
+    fn test_error_policy_without_errors_agrees_with_resolve() {
+        use DependentEffect::*;
+        use TestExpression::*;
+        let effect = Disjoint(vec![
+            Aggregate(vec![
+                Atomic(Effect::ALLOW, Match),
+                Atomic(Effect::DENY, Miss),
+            ]),
+            Aggregate(vec![Fixed(Effect::ALLOW), Silent]),
+        ]);
+
+        for policy in [
+            ErrorPolicy::FailFast,
+            ErrorPolicy::ErrorAsDeny,
+            ErrorPolicy::ErrorAsSilent,
+            ErrorPolicy::Indeterminate,
+        ] {
+            let actual = effect.resolve_with_policy(&TestEnv, policy);
+            assert_eq!(actual, Ok(ALLOW.into()));
+        }
+    }
 }
@@ -1,6 +1,10 @@
 //! Authorization effects.
 //!
 
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
 /// Definite authorization
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Effect {
@@ -165,7 +169,574 @@ where
         .unwrap_or(SILENT)
 }
 
+/// Priority of a constituent in a prioritized combination. Higher priorities
+/// take precedence.
+pub type Priority = u32;
+
+/// Combine prioritized computed effects. The result is the effect of the
+/// highest-priority constituents that are not silent, whatever their effect.
+/// Ties are broken deterministically: constituents sharing that priority are
+/// combined non-strictly so any `DENY` among them wins regardless of order.
+///
+/// The result is `SILENT` if there are no constituents or if all constituents
+/// are silence.
+///
+/// # Examples
+///
+/// ```
+/// use authorization_core::effect::*;
+///
+/// // silence if no constituents
+/// assert_eq!(SILENT, combine_prioritized(vec![]));
+///
+/// // the highest priority wins, even over DENY
+/// assert_eq!(ALLOW, combine_prioritized(vec![(1, DENY), (10, ALLOW)]));
+///
+/// // silence is not applicable whatever its priority
+/// assert_eq!(DENY, combine_prioritized(vec![(1, DENY), (10, SILENT)]));
+///
+/// // DENY breaks ties
+/// assert_eq!(DENY, combine_prioritized(vec![(10, ALLOW), (10, DENY)]));
+/// ```
+pub fn combine_prioritized<I>(effs: I) -> ComputedEffect
+where
+    I: IntoIterator<Item = (Priority, ComputedEffect)>,
+{
+    effs.into_iter()
+        .filter(|(_, e)| *e != SILENT)
+        .fold(None, |a, (p, e)| match a {
+            Some((q, x)) if q > p => Some((q, x)),
+            Some((q, x)) if q == p => Some((q, combine_non_strict([x, e]))),
+            _ => Some((p, e)),
+        })
+        .map_or(SILENT, |(_, e)| e)
+}
+
+/// Group the positions of prioritized constituents by descending priority.
+/// Positions within a group are in order.
+pub(crate) fn priority_groups<I>(priorities: I) -> Vec<Vec<usize>>
+where
+    I: IntoIterator<Item = Priority>,
+{
+    let mut indexed: Vec<(Priority, usize)> = priorities
+        .into_iter()
+        .enumerate()
+        .map(|(i, p)| (p, i))
+        .collect();
+    indexed.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut last = None;
+    for (p, i) in indexed {
+        if last == Some(p) {
+            groups.last_mut().unwrap().push(i);
+        } else {
+            groups.push(vec![i]);
+            last = Some(p);
+        }
+    }
+    groups
+}
+
+/// Algorithm combining the effects of several constituents in order. Silence
+/// means a constituent is not applicable.
+pub trait CombiningAlgorithm {
+    /// Combine computed effects.
+    fn combine(&self, effs: &[ComputedEffect]) -> ComputedEffect;
+
+    /// The combined effect if the first effects decide it whatever the effects
+    /// that follow. Returning `None` is always correct but prevents stopping early.
+    fn decided(&self, _prefix: &[ComputedEffect]) -> Option<ComputedEffect> {
+        None
+    }
+
+    /// Indices of the effects that decided the combination. By default those
+    /// equal to the combined effect.
+    fn deciding(&self, effs: &[ComputedEffect]) -> Vec<usize> {
+        let combined = self.combine(effs);
+        (0..effs.len()).filter(|i| effs[*i] == combined).collect()
+    }
+}
+
+/// Private helper. Position of the first effect that is not silence.
+fn first_applicable(effs: &[ComputedEffect]) -> Option<usize> {
+    effs.iter().position(|e| *e != SILENT)
+}
+
+/// Combines with `combine_non_strict(_)`: `DENY` overrides and silence is ignored.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Hash)]
+pub struct NonStrict;
+
+impl CombiningAlgorithm for NonStrict {
+    fn combine(&self, effs: &[ComputedEffect]) -> ComputedEffect {
+        combine_non_strict(effs.iter().copied())
+    }
+
+    fn decided(&self, prefix: &[ComputedEffect]) -> Option<ComputedEffect> {
+        prefix.contains(&DENY).then_some(DENY)
+    }
+}
+
+/// Combines with `combine_strict(_)`: silence overrides, then `DENY`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Hash)]
+pub struct Strict;
+
+impl CombiningAlgorithm for Strict {
+    fn combine(&self, effs: &[ComputedEffect]) -> ComputedEffect {
+        combine_strict(effs.iter().copied())
+    }
+
+    fn decided(&self, prefix: &[ComputedEffect]) -> Option<ComputedEffect> {
+        prefix.contains(&SILENT).then_some(SILENT)
+    }
+}
+
+/// `ALLOW` overrides, then `DENY`. Silence is ignored.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Hash)]
+pub struct PermitOverrides;
+
+impl CombiningAlgorithm for PermitOverrides {
+    fn combine(&self, effs: &[ComputedEffect]) -> ComputedEffect {
+        if effs.contains(&ALLOW) {
+            ALLOW
+        } else if effs.contains(&DENY) {
+            DENY
+        } else {
+            SILENT
+        }
+    }
+
+    fn decided(&self, prefix: &[ComputedEffect]) -> Option<ComputedEffect> {
+        prefix.contains(&ALLOW).then_some(ALLOW)
+    }
+}
+
+/// The first effect that is not silence.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Hash)]
+pub struct FirstApplicable;
+
+impl CombiningAlgorithm for FirstApplicable {
+    fn combine(&self, effs: &[ComputedEffect]) -> ComputedEffect {
+        first_applicable(effs).map_or(SILENT, |i| effs[i])
+    }
+
+    fn decided(&self, prefix: &[ComputedEffect]) -> Option<ComputedEffect> {
+        first_applicable(prefix).map(|i| prefix[i])
+    }
+
+    fn deciding(&self, effs: &[ComputedEffect]) -> Vec<usize> {
+        first_applicable(effs).into_iter().collect()
+    }
+}
+
+/// The only effect that is not silence. More than one such effect is an error
+/// in the configuration and results in `DENY`, decided by none of the effects.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Hash)]
+pub struct OnlyOneApplicable;
+
+impl CombiningAlgorithm for OnlyOneApplicable {
+    fn combine(&self, effs: &[ComputedEffect]) -> ComputedEffect {
+        let mut applicable = effs.iter().filter(|e| **e != SILENT);
+        match (applicable.next(), applicable.next()) {
+            (None, _) => SILENT,
+            (Some(e), None) => *e,
+            (Some(_), Some(_)) => DENY,
+        }
+    }
+
+    fn decided(&self, prefix: &[ComputedEffect]) -> Option<ComputedEffect> {
+        (prefix.iter().filter(|e| **e != SILENT).count() > 1).then_some(DENY)
+    }
+
+    fn deciding(&self, effs: &[ComputedEffect]) -> Vec<usize> {
+        let applicable: Vec<usize> = (0..effs.len()).filter(|i| effs[*i] != SILENT).collect();
+        if applicable.len() == 1 {
+            applicable
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// `ALLOW` if any effect is `ALLOW`, otherwise `DENY`. Never silent.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Hash)]
+pub struct DenyUnlessPermit;
+
+impl CombiningAlgorithm for DenyUnlessPermit {
+    fn combine(&self, effs: &[ComputedEffect]) -> ComputedEffect {
+        if effs.contains(&ALLOW) {
+            ALLOW
+        } else {
+            DENY
+        }
+    }
+
+    fn decided(&self, prefix: &[ComputedEffect]) -> Option<ComputedEffect> {
+        prefix.contains(&ALLOW).then_some(ALLOW)
+    }
+}
+
+/// `DENY` if any effect is `DENY`, otherwise `ALLOW`. Never silent.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Hash)]
+pub struct PermitUnlessDeny;
+
+impl CombiningAlgorithm for PermitUnlessDeny {
+    fn combine(&self, effs: &[ComputedEffect]) -> ComputedEffect {
+        if effs.contains(&DENY) {
+            DENY
+        } else {
+            ALLOW
+        }
+    }
+
+    fn decided(&self, prefix: &[ComputedEffect]) -> Option<ComputedEffect> {
+        prefix.contains(&DENY).then_some(DENY)
+    }
+}
+
+/// User-defined combining algorithm, shared so that policies and dependent
+/// effects carrying it stay cheap to clone. Two values are equal if they share
+/// the same instance.
+///
+/// # Examples
+///
+/// ```
+/// use authorization_core::effect::*;
+///
+/// /// ALLOW only if at least two constituents allow, otherwise DENY.
+/// struct TwoPersonRule;
+///
+/// impl CombiningAlgorithm for TwoPersonRule {
+///     fn combine(&self, effs: &[ComputedEffect]) -> ComputedEffect {
+///         if effs.iter().filter(|e| **e == ALLOW).count() >= 2 {
+///             ALLOW
+///         } else {
+///             DENY
+///         }
+///     }
+/// }
+///
+/// let algorithm = Algorithm::Custom(CustomAlgorithm::new(TwoPersonRule));
+///
+/// assert_eq!(algorithm.combine(&[ALLOW, SILENT, ALLOW]), ALLOW);
+/// assert_eq!(algorithm.combine(&[ALLOW, SILENT]), DENY);
+/// assert_eq!(algorithm, algorithm.clone());
+/// assert_ne!(algorithm, Algorithm::Custom(CustomAlgorithm::new(TwoPersonRule)));
+/// ```
+#[derive(Clone)]
+pub struct CustomAlgorithm(Arc<dyn CombiningAlgorithm + Send + Sync>);
+
+impl CustomAlgorithm {
+    pub fn new<A>(algorithm: A) -> Self
+    where
+        A: CombiningAlgorithm + Send + Sync + 'static,
+    {
+        CustomAlgorithm(Arc::new(algorithm))
+    }
+}
+
+impl From<Arc<dyn CombiningAlgorithm + Send + Sync>> for CustomAlgorithm {
+    fn from(algorithm: Arc<dyn CombiningAlgorithm + Send + Sync>) -> Self {
+        CustomAlgorithm(algorithm)
+    }
+}
+
+impl PartialEq for CustomAlgorithm {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CustomAlgorithm {}
+
+impl Hash for CustomAlgorithm {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).cast::<()>().hash(state)
+    }
+}
+
+impl fmt::Debug for CustomAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CustomAlgorithm(..)")
+    }
+}
+
+impl CombiningAlgorithm for CustomAlgorithm {
+    fn combine(&self, effs: &[ComputedEffect]) -> ComputedEffect {
+        self.0.combine(effs)
+    }
+
+    fn decided(&self, prefix: &[ComputedEffect]) -> Option<ComputedEffect> {
+        self.0.decided(prefix)
+    }
+
+    fn deciding(&self, effs: &[ComputedEffect]) -> Vec<usize> {
+        self.0.deciding(effs)
+    }
+}
+
+/// Choice of combining algorithm, as carried by combining policies and
+/// dependent effects: one of the standard algorithms or a user-defined one.
+///
+/// # Examples
+///
+/// ```
+/// use authorization_core::effect::*;
+///
+/// let effs = [SILENT, DENY, ALLOW];
+///
+/// assert_eq!(Algorithm::NonStrict.combine(&effs), DENY);
+/// assert_eq!(Algorithm::PermitOverrides.combine(&effs), ALLOW);
+/// assert_eq!(Algorithm::FirstApplicable.combine(&effs), DENY);
+/// assert_eq!(Algorithm::OnlyOneApplicable.combine(&[SILENT, ALLOW]), ALLOW);
+/// assert_eq!(Algorithm::DenyUnlessPermit.combine(&[SILENT]), DENY);
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Default, Hash)]
+pub enum Algorithm {
+    #[default]
+    NonStrict,
+    Strict,
+    PermitOverrides,
+    FirstApplicable,
+    OnlyOneApplicable,
+    DenyUnlessPermit,
+    PermitUnlessDeny,
+    Custom(CustomAlgorithm),
+}
+
+impl Algorithm {
+    /// Every standard algorithm.
+    pub const ALL: [Algorithm; 7] = [
+        Algorithm::NonStrict,
+        Algorithm::Strict,
+        Algorithm::PermitOverrides,
+        Algorithm::FirstApplicable,
+        Algorithm::OnlyOneApplicable,
+        Algorithm::DenyUnlessPermit,
+        Algorithm::PermitUnlessDeny,
+    ];
+
+    /// Private helper. The algorithm implementation.
+    fn algorithm(&self) -> &dyn CombiningAlgorithm {
+        use Algorithm::*;
+        match self {
+            NonStrict => &self::NonStrict,
+            Strict => &self::Strict,
+            PermitOverrides => &self::PermitOverrides,
+            FirstApplicable => &self::FirstApplicable,
+            OnlyOneApplicable => &self::OnlyOneApplicable,
+            DenyUnlessPermit => &self::DenyUnlessPermit,
+            PermitUnlessDeny => &self::PermitUnlessDeny,
+            Custom(custom) => custom,
+        }
+    }
+}
+
+impl CombiningAlgorithm for Algorithm {
+    fn combine(&self, effs: &[ComputedEffect]) -> ComputedEffect {
+        self.algorithm().combine(effs)
+    }
+
+    fn decided(&self, prefix: &[ComputedEffect]) -> Option<ComputedEffect> {
+        self.algorithm().decided(prefix)
+    }
+
+    fn deciding(&self, effs: &[ComputedEffect]) -> Vec<usize> {
+        self.algorithm().deciding(effs)
+    }
+}
+
+/// Outcome of an authorization computation that tolerates failures to evaluate
+/// conditions, in the style of XACML "indeterminate" decisions. An outcome is the
+/// set of effects the computation could have produced had every condition been
+/// evaluated. It is determined if there is only one possibility.
+///
+/// # Examples
+///
+/// ```
+/// use authorization_core::effect::*;
+///
+/// // a failed condition guarding a DENY could have been DENY or silence
+/// let failed = Outcome::indeterminate(Effect::DENY);
+/// assert!(failed.could_be(DENY) && failed.could_be(SILENT));
+///
+/// // but it cannot change a combination that is already denied
+/// assert_eq!(combine_outcomes_non_strict(vec![failed, DENY.into()]).effect(), Some(DENY));
+///
+/// // it can change one that is allowed
+/// let combined = combine_outcomes_non_strict(vec![failed, ALLOW.into()]);
+/// assert!(combined.is_indeterminate());
+/// assert!(!combined.authorized());
+/// ```
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub struct Outcome {
+    silent: bool,
+    allow: bool,
+    deny: bool,
+}
+
+impl Outcome {
+    /// Outcome of a failed condition guarding an effect: the effect or silence.
+    pub fn indeterminate(effect: Effect) -> Self {
+        Outcome::from(SILENT).or(ComputedEffect::from(effect).into())
+    }
+
+    /// Determine if the computation could have produced an effect.
+    pub fn could_be(self, effect: ComputedEffect) -> bool {
+        match effect.0 {
+            None => self.silent,
+            Some(Effect::ALLOW) => self.allow,
+            Some(Effect::DENY) => self.deny,
+        }
+    }
+
+    /// The effect, if the outcome is determined.
+    pub fn effect(self) -> Option<ComputedEffect> {
+        match (self.silent, self.allow, self.deny) {
+            (true, false, false) => Some(SILENT),
+            (false, true, false) => Some(ALLOW),
+            (false, false, true) => Some(DENY),
+            _ => None,
+        }
+    }
+
+    /// Determine if the outcome has more than one possible effect.
+    pub fn is_indeterminate(self) -> bool {
+        self.effect().is_none()
+    }
+
+    /// Enumerate the possible effects.
+    pub fn possibilities(self) -> impl Iterator<Item = ComputedEffect> {
+        [SILENT, ALLOW, DENY]
+            .into_iter()
+            .filter(move |e| self.could_be(*e))
+    }
+
+    /// Determine if the outcome authorizes access. Only a determined `ALLOW`
+    /// authorizes access.
+    pub fn authorized(self) -> bool {
+        self.effect() == Some(ALLOW)
+    }
+
+    /// Private helper. Union of possibilities.
+    fn or(self, other: Outcome) -> Outcome {
+        Outcome {
+            silent: self.silent || other.silent,
+            allow: self.allow || other.allow,
+            deny: self.deny || other.deny,
+        }
+    }
+
+    /// Private helper. Possible results of combining every pair of possibilities.
+    fn combine<F>(self, other: Outcome, combine: F) -> Outcome
+    where
+        F: Fn(ComputedEffect, ComputedEffect) -> ComputedEffect,
+    {
+        let mut combined = Outcome {
+            silent: false,
+            allow: false,
+            deny: false,
+        };
+        for a in self.possibilities() {
+            for b in other.possibilities() {
+                combined = combined.or(combine(a, b).into());
+            }
+        }
+        combined
+    }
+}
+
+impl From<ComputedEffect> for Outcome {
+    fn from(effect: ComputedEffect) -> Self {
+        Outcome {
+            silent: effect == SILENT,
+            allow: effect == ALLOW,
+            deny: effect == DENY,
+        }
+    }
+}
+
+/// Combine outcomes in non-strict fashion. The possibilities are those of
+/// `combine_non_strict(_)` applied to every combination of constituent
+/// possibilities so e.g. a determined `DENY` constituent decides the result.
+pub fn combine_outcomes_non_strict<I>(outcomes: I) -> Outcome
+where
+    I: IntoIterator<Item = Outcome>,
+{
+    outcomes.into_iter().fold(SILENT.into(), |a, o| {
+        a.combine(o, |x, y| combine_non_strict([x, y]))
+    })
+}
+
+/// Combine outcomes in strict fashion. The possibilities are those of
+/// `combine_strict(_)` applied to every combination of constituent
+/// possibilities so e.g. a determined `SILENT` constituent decides the result.
+pub fn combine_outcomes_strict<I>(outcomes: I) -> Outcome
+where
+    I: IntoIterator<Item = Outcome>,
+{
+    outcomes
+        .into_iter()
+        .reduce(|a, o| a.combine(o, |x, y| combine_strict([x, y])))
+        .unwrap_or_else(|| SILENT.into())
+}
+
+/// Combine prioritized outcomes. The possibilities are those of
+/// `combine_prioritized(_)` applied to every combination of constituent
+/// possibilities so e.g. an indeterminate constituent only affects the result
+/// if every constituent of higher priority could be silent.
+pub fn combine_outcomes_prioritized<I>(outcomes: I) -> Outcome
+where
+    I: IntoIterator<Item = (Priority, Outcome)>,
+{
+    let outcomes: Vec<(Priority, Outcome)> = outcomes.into_iter().collect();
+    let groups: Vec<Outcome> = priority_groups(outcomes.iter().map(|(p, _)| *p))
+        .into_iter()
+        .map(|group| combine_outcomes_non_strict(group.into_iter().map(|i| outcomes[i].1)))
+        .collect();
+    combine_outcomes(&FirstApplicable, &groups)
+}
+
+/// Combine outcomes with a combining algorithm. The possibilities are those of
+/// the algorithm applied to every combination of constituent possibilities, so
+/// the cost grows exponentially with the number of indeterminate constituents
+/// the algorithm cannot decide without.
+pub fn combine_outcomes<A>(algorithm: &A, outcomes: &[Outcome]) -> Outcome
+where
+    A: CombiningAlgorithm + ?Sized,
+{
+    fn visit<A: CombiningAlgorithm + ?Sized>(
+        algorithm: &A,
+        outcomes: &[Outcome],
+        prefix: &mut Vec<ComputedEffect>,
+        combined: &mut Outcome,
+    ) {
+        if let Some(decided) = algorithm.decided(prefix) {
+            *combined = combined.or(decided.into());
+            return;
+        }
+        match outcomes.split_first() {
+            None => *combined = combined.or(algorithm.combine(prefix).into()),
+            Some((first, rest)) => {
+                for e in first.possibilities() {
+                    prefix.push(e);
+                    visit(algorithm, rest, prefix, combined);
+                    prefix.pop();
+                }
+            }
+        }
+    }
+
+    let mut combined = Outcome {
+        silent: false,
+        allow: false,
+        deny: false,
+    };
+    visit(algorithm, outcomes, &mut Vec::new(), &mut combined);
+    combined
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
@@ -245,4 +816,299 @@ mod tests {
         check(vec![SILENT, DENY, SILENT, ALLOW, SILENT], SILENT);
         check(vec![SILENT, ALLOW, SILENT, ALLOW, SILENT], SILENT);
     }
+
+    #[test]
+    fn test_outcome_determined() {
+        for effect in [SILENT, ALLOW, DENY] {
+            let outcome = Outcome::from(effect);
+            assert_eq!(outcome.effect(), Some(effect));
+            assert!(!outcome.is_indeterminate());
+            assert_eq!(outcome.possibilities().collect::<Vec<_>>(), vec![effect]);
+        }
+        assert!(Outcome::from(ALLOW).authorized());
+    }
+
+    #[test]
+    fn test_outcome_indeterminate() {
+        let outcome = Outcome::indeterminate(Effect::ALLOW);
+
+        assert!(outcome.is_indeterminate());
+        assert!(!outcome.authorized());
+        assert_eq!(
+            outcome.possibilities().collect::<Vec<_>>(),
+            vec![SILENT, ALLOW]
+        );
+    }
+
+    #[test]
+    fn test_combine_outcomes_non_strict() {
+        let maybe_allow = Outcome::indeterminate(Effect::ALLOW);
+        let maybe_deny = Outcome::indeterminate(Effect::DENY);
+
+        assert_eq!(combine_outcomes_non_strict(vec![]), SILENT.into());
+        assert_eq!(
+            combine_outcomes_non_strict(vec![maybe_allow, ALLOW.into()]),
+            ALLOW.into()
+        );
+        assert_eq!(
+            combine_outcomes_non_strict(vec![maybe_deny, maybe_allow, DENY.into()]),
+            DENY.into()
+        );
+        assert_eq!(
+            combine_outcomes_non_strict(vec![maybe_deny, SILENT.into()]),
+            maybe_deny
+        );
+
+        let actual = combine_outcomes_non_strict(vec![maybe_deny, maybe_allow]);
+        assert_eq!(
+            actual.possibilities().collect::<Vec<_>>(),
+            vec![SILENT, ALLOW, DENY]
+        );
+
+        let actual = combine_outcomes_non_strict(vec![maybe_deny, ALLOW.into()]);
+        assert_eq!(
+            actual.possibilities().collect::<Vec<_>>(),
+            vec![ALLOW, DENY]
+        );
+    }
+
+    #[test]
+    fn test_combine_outcomes_strict() {
+        let maybe_allow = Outcome::indeterminate(Effect::ALLOW);
+        let maybe_deny = Outcome::indeterminate(Effect::DENY);
+
+        assert_eq!(combine_outcomes_strict(vec![]), SILENT.into());
+        assert_eq!(
+            combine_outcomes_strict(vec![maybe_allow, SILENT.into()]),
+            SILENT.into()
+        );
+        assert_eq!(
+            combine_outcomes_strict(vec![maybe_allow, ALLOW.into()]),
+            maybe_allow
+        );
+        assert_eq!(
+            combine_outcomes_strict(vec![maybe_deny, ALLOW.into()]),
+            maybe_deny
+        );
+    }
+
+    #[test]
+    fn test_combine_outcomes_agrees_with_effects() {
+        let effects = [SILENT, ALLOW, DENY];
+        for a in effects {
+            for b in effects {
+                for c in effects {
+                    let outcomes = [a.into(), b.into(), c.into()];
+                    assert_eq!(
+                        combine_outcomes_non_strict(outcomes),
+                        combine_non_strict([a, b, c]).into()
+                    );
+                    assert_eq!(
+                        combine_outcomes_strict(outcomes),
+                        combine_strict([a, b, c]).into()
+                    );
+                }
+            }
+        }
+    }
+
+    /// Private helper. Every sequence of effects up to a length.
+    fn sequences(len: usize) -> Vec<Vec<ComputedEffect>> {
+        let mut all = vec![vec![]];
+        let mut last = vec![vec![]];
+        for _ in 0..len {
+            last = last
+                .iter()
+                .flat_map(|s: &Vec<ComputedEffect>| {
+                    [SILENT, ALLOW, DENY].map(|e| {
+                        let mut s = s.clone();
+                        s.push(e);
+                        s
+                    })
+                })
+                .collect();
+            all.extend(last.iter().cloned());
+        }
+        all
+    }
+
+    #[test]
+    fn test_algorithms() {
+        use Algorithm::*;
+        fn check(algorithm: Algorithm, effs: &[ComputedEffect], expected: ComputedEffect) {
+            assert_eq!(
+                algorithm.combine(effs),
+                expected,
+                "{:?} {:?}",
+                algorithm,
+                effs
+            );
+        }
+
+        check(PermitOverrides, &[DENY, ALLOW, DENY], ALLOW);
+        check(PermitOverrides, &[DENY, SILENT], DENY);
+        check(PermitOverrides, &[SILENT], SILENT);
+
+        check(FirstApplicable, &[SILENT, ALLOW, DENY], ALLOW);
+        check(FirstApplicable, &[SILENT, DENY, ALLOW], DENY);
+        check(FirstApplicable, &[], SILENT);
+
+        check(OnlyOneApplicable, &[SILENT, DENY, SILENT], DENY);
+        check(OnlyOneApplicable, &[ALLOW, SILENT, ALLOW], DENY);
+        check(OnlyOneApplicable, &[SILENT], SILENT);
+
+        check(DenyUnlessPermit, &[DENY, ALLOW], ALLOW);
+        check(DenyUnlessPermit, &[], DENY);
+
+        check(PermitUnlessDeny, &[ALLOW, DENY], DENY);
+        check(PermitUnlessDeny, &[SILENT], ALLOW);
+    }
+
+    #[test]
+    fn test_algorithms_agree_with_combine_functions() {
+        for effs in sequences(4) {
+            assert_eq!(
+                Algorithm::NonStrict.combine(&effs),
+                combine_non_strict(effs.clone())
+            );
+            assert_eq!(
+                Algorithm::Strict.combine(&effs),
+                combine_strict(effs.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_decided_is_final() {
+        for algorithm in Algorithm::ALL {
+            for prefix in sequences(3) {
+                if let Some(decided) = algorithm.decided(&prefix) {
+                    for rest in sequences(2) {
+                        let effs = [prefix.clone(), rest].concat();
+                        assert_eq!(
+                            algorithm.combine(&effs),
+                            decided,
+                            "{:?} {:?}",
+                            algorithm,
+                            effs
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_deciding() {
+        use Algorithm::*;
+
+        assert_eq!(NonStrict.deciding(&[ALLOW, DENY, SILENT, DENY]), vec![1, 3]);
+        assert_eq!(FirstApplicable.deciding(&[SILENT, ALLOW, ALLOW]), vec![1]);
+        assert_eq!(OnlyOneApplicable.deciding(&[SILENT, ALLOW]), vec![1]);
+        assert!(OnlyOneApplicable
+            .deciding(&[ALLOW, SILENT, ALLOW])
+            .is_empty());
+        assert!(OnlyOneApplicable.deciding(&[DENY, ALLOW]).is_empty());
+        assert!(DenyUnlessPermit.deciding(&[SILENT]).is_empty());
+    }
+
+    #[test]
+    fn test_combine_outcomes() {
+        let maybe_allow = Outcome::indeterminate(Effect::ALLOW);
+        let maybe_deny = Outcome::indeterminate(Effect::DENY);
+
+        let actual = combine_outcomes(&FirstApplicable, &[ALLOW.into(), maybe_deny]);
+        assert_eq!(actual, ALLOW.into());
+
+        let actual = combine_outcomes(&FirstApplicable, &[maybe_deny, ALLOW.into()]);
+        assert_eq!(
+            actual.possibilities().collect::<Vec<_>>(),
+            vec![ALLOW, DENY]
+        );
+
+        let actual = combine_outcomes(&DenyUnlessPermit, &[maybe_allow]);
+        assert_eq!(
+            actual.possibilities().collect::<Vec<_>>(),
+            vec![ALLOW, DENY]
+        );
+
+        let actual = combine_outcomes(&OnlyOneApplicable, &[maybe_allow, maybe_allow]);
+        assert_eq!(
+            actual.possibilities().collect::<Vec<_>>(),
+            vec![SILENT, ALLOW, DENY]
+        );
+
+        for outcomes in [
+            vec![maybe_allow, maybe_deny],
+            vec![maybe_deny, DENY.into()],
+            vec![],
+        ] {
+            assert_eq!(
+                combine_outcomes(&NonStrict, &outcomes),
+                combine_outcomes_non_strict(outcomes.clone())
+            );
+            assert_eq!(
+                combine_outcomes(&Strict, &outcomes),
+                combine_outcomes_strict(outcomes.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_combine_prioritized() {
+        let priorities = [2, 1, 2, 0];
+        for effs in sequences(4).into_iter().filter(|s| s.len() == 4) {
+            let prioritized = priorities.iter().copied().zip(effs.iter().copied());
+
+            // the highest priority group that is not silent decides
+            let expected = [2, 1, 0]
+                .iter()
+                .map(|p| {
+                    combine_non_strict(prioritized.clone().filter(|(q, _)| q == p).map(|(_, e)| e))
+                })
+                .find(|e| *e != SILENT)
+                .unwrap_or(SILENT);
+            assert_eq!(
+                combine_prioritized(prioritized.clone()),
+                expected,
+                "{:?}",
+                effs
+            );
+
+            // order does not matter
+            let reversed: Vec<_> = prioritized.rev().collect();
+            assert_eq!(combine_prioritized(reversed), expected, "{:?}", effs);
+        }
+    }
+
+    #[test]
+    fn test_priority_groups() {
+        assert_eq!(
+            priority_groups([1, 3, 1, 2, 3]),
+            vec![vec![1, 4], vec![3], vec![0, 2]]
+        );
+        assert!(priority_groups([]).is_empty());
+    }
+
+    #[test]
+    fn test_combine_outcomes_prioritized() {
+        let maybe_allow = Outcome::indeterminate(Effect::ALLOW);
+
+        let actual = combine_outcomes_prioritized([(1, maybe_allow), (2, DENY.into())]);
+        assert_eq!(actual, DENY.into());
+
+        let actual = combine_outcomes_prioritized([(2, maybe_allow), (1, DENY.into())]);
+        assert_eq!(
+            actual.possibilities().collect::<Vec<_>>(),
+            vec![ALLOW, DENY]
+        );
+
+        for effs in sequences(3).into_iter().filter(|s| s.len() == 3) {
+            let prioritized: Vec<_> = [1, 2, 1].into_iter().zip(effs).collect();
+            assert_eq!(
+                combine_outcomes_prioritized(prioritized.iter().map(|(p, e)| (*p, (*e).into()))),
+                combine_prioritized(prioritized.clone()).into()
+            );
+        }
+    }
 }
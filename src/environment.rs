@@ -2,6 +2,8 @@
 //!
 //!
 
+use std::future::{ready, Future, Ready};
+
 /// Contextual computations. An environment is considered unreliable generally
 /// so its methods return a `Result` for error signaling.
 pub trait Environment {
@@ -38,6 +40,46 @@ where
     }
 }
 
+/// Contextual computations for environments that test conditions asynchronously
+/// e.g. by querying a database or directory service. The trait does not depend on
+/// any particular executor.
+///
+/// Every `Environment` is an `AsyncEnvironment` whose tests complete immediately.
+pub trait AsyncEnvironment {
+    /// The type of error produced by this environment.
+    type Err;
+
+    /// The type of conditional expression that can be evaluated in the environment.
+    type CExp;
+
+    /// The future produced when testing a condition. Implementations based on
+    /// `async` blocks can use a boxed future e.g.
+    /// `Pin<Box<dyn Future<Output = Result<bool, Self::Err>> + Send + 'a>>`.
+    type Test<'a>: Future<Output = Result<bool, Self::Err>> + 'a
+    where
+        Self: 'a;
+
+    /// Test that a condition holds with respect to the environment. The future
+    /// can resolve to `Err(_)` if an environmental error is encountered.
+    fn test_condition_async<'a>(&'a self, exp: &'a Self::CExp) -> Self::Test<'a>;
+}
+
+impl<T> AsyncEnvironment for T
+where
+    T: Environment,
+{
+    type Err = <Self as Environment>::Err;
+    type CExp = <Self as Environment>::CExp;
+    type Test<'a>
+        = Ready<Result<bool, Self::Err>>
+    where
+        Self: 'a;
+
+    fn test_condition_async<'a>(&'a self, exp: &'a Self::CExp) -> Self::Test<'a> {
+        ready(self.test_condition(exp))
+    }
+}
+
 /// Environment in which conditions always match and evaluations never fail.
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub struct PositiveEnvironment;
@@ -65,6 +107,13 @@ impl ReliableEnvironment for NegativeEnvironment {
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub struct FailingEnvironment<Err>(Err);
 
+impl<E> FailingEnvironment<E> {
+    /// Create an environment failing with an error.
+    pub fn new(err: E) -> Self {
+        FailingEnvironment(err)
+    }
+}
+
 impl<E> Environment for FailingEnvironment<E>
 where
     E: Clone,
@@ -77,7 +126,26 @@ where
     }
 }
 
+/// Environment testing borrowed conditions, e.g. those of the dependent effects
+/// produced by `Policy::apply_ref`, in an environment for the conditions.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Borrowed<'a, E>(pub &'a E);
+
+impl<'a, E> Environment for Borrowed<'a, E>
+where
+    E: Environment,
+    E::CExp: 'a,
+{
+    type CExp = &'a E::CExp;
+    type Err = E::Err;
+
+    fn test_condition(&self, exp: &Self::CExp) -> Result<bool, Self::Err> {
+        self.0.test_condition(exp)
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
@@ -109,4 +177,16 @@ mod tests {
         let env = &FailingEnvironment("Whoops");
         assert_eq!(env.test_condition(&()), Err("Whoops"));
     }
+
+    #[test]
+    pub fn test_borrowed_environment() {
+        assert_eq!(
+            Borrowed(&PositiveEnvironment).test_condition(&&()),
+            Ok(true)
+        );
+        assert_eq!(
+            Borrowed(&FailingEnvironment("Whoops")).test_condition(&&()),
+            Err("Whoops")
+        );
+    }
 }
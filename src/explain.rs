@@ -0,0 +1,673 @@
+//! Explanations of resolved effects.
+//!
+//! `DependentEffect::resolve_explained` records a `Trace` mirroring the
+//! structure of the dependent effect, so a decision can be traced back to the
+//! conditions and constituents that produced it.
+
+use std::fmt;
+
+use super::dependent_effect::*;
+use super::effect::*;
+use super::environment::*;
+use super::obligation::Duties;
+
+/// Record of resolving a dependent effect.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Trace<'a, CExp> {
+    /// The effect the node resolved to.
+    pub effect: ComputedEffect,
+    /// What was done to resolve the node.
+    pub node: TraceNode<'a, CExp>,
+}
+
+/// Resolution of a single dependent effect node.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TraceNode<'a, CExp> {
+    /// An unconditional silence.
+    Silent,
+    /// An unconditional effect.
+    Fixed(Effect),
+    /// A conditional effect, with the condition tested and whether it held.
+    Atomic {
+        effect: Effect,
+        condition: &'a CExp,
+        held: bool,
+    },
+    /// A non-strict combination with the traces of its constituents and the
+    /// indices of those that decided the result.
+    Aggregate {
+        children: Vec<Trace<'a, CExp>>,
+        deciding: Vec<usize>,
+    },
+    /// A strict combination with the traces of its constituents and the indices
+    /// of those that decided the result.
+    Disjoint {
+        children: Vec<Trace<'a, CExp>>,
+        deciding: Vec<usize>,
+    },
+    /// A combination using a combining algorithm with the traces of its
+    /// constituents and the indices of those that decided the result.
+    Combined {
+        algorithm: &'a Algorithm,
+        children: Vec<Trace<'a, CExp>>,
+        deciding: Vec<usize>,
+    },
+    /// A prioritized combination with the traces of its constituents and their
+    /// priorities, and the indices of those that decided the result.
+    Prioritized {
+        children: Vec<(Priority, Trace<'a, CExp>)>,
+        deciding: Vec<usize>,
+    },
+    /// An effect with attached duties and the trace of the effect, which always
+    /// decides the node.
+    Obligated {
+        duties: &'a Duties,
+        child: Box<Trace<'a, CExp>>,
+    },
+    /// An identified statement and the trace of its effect, which always
+    /// decides the node.
+    Identified {
+        id: &'a str,
+        child: Box<Trace<'a, CExp>>,
+    },
+}
+
+impl<CExp> DependentEffect<CExp> {
+    /// Evaluate dependent effect in an envionmental context, recording how the
+    /// effect was reached.
+    ///
+    /// The children deciding an `Aggregate` are its `DENY` constituents if it
+    /// resolves to `DENY`, otherwise those with the resolved effect. The children
+    /// deciding a `Disjoint` are its `SILENT` constituents if it resolves to
+    /// `SILENT`, otherwise those with the resolved effect. The children deciding
+    /// a `Combined` are given by its algorithm. The children deciding a
+    /// `Prioritized` are those with the resolved effect and, unless it resolves
+    /// to `SILENT`, the highest priority of any effect.
+    ///
+    /// Recording costs more than `resolve_short_circuit`: every condition is
+    /// tested, even once a combination is decided, and a trace node is
+    /// allocated for every node of the effect. Resolutions reading the trace,
+    /// e.g. `resolve_with_duties` and `resolve_attributed`, share this cost.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use authorization_core::dependent_effect::*;
+    /// use authorization_core::effect::*;
+    /// use authorization_core::environment::*;
+    ///
+    /// let effect = DependentEffect::Aggregate(vec![
+    ///     DependentEffect::Fixed(Effect::ALLOW),
+    ///     DependentEffect::Atomic(Effect::DENY, ()),
+    /// ]);
+    ///
+    /// let (resolved, trace) = effect.resolve_explained(&PositiveEnvironment).unwrap();
+    ///
+    /// assert_eq!(resolved, DENY);
+    /// assert_eq!(
+    ///     trace.render_with(|_| "always".to_string()),
+    ///     "DENY aggregate\n  ALLOW fixed ALLOW\n* DENY DENY if always: held\n"
+    /// );
+    /// ```
+    pub fn resolve_explained<Env>(
+        &self,
+        environment: &Env,
+    ) -> Result<(ComputedEffect, Trace<'_, CExp>), Env::Err>
+    where
+        Env: Environment<CExp = CExp>,
+    {
+        let trace = self.trace(environment)?;
+        Ok((trace.effect, trace))
+    }
+
+    /// Private helper. Resolve and record a node.
+    fn trace<Env>(&self, environment: &Env) -> Result<Trace<'_, CExp>, Env::Err>
+    where
+        Env: Environment<CExp = CExp>,
+    {
+        use DependentEffect::*;
+        let trace = match self {
+            Silent => Trace {
+                effect: SILENT,
+                node: TraceNode::Silent,
+            },
+            Fixed(eff) => Trace {
+                effect: (*eff).into(),
+                node: TraceNode::Fixed(*eff),
+            },
+            Atomic(eff, cexp) => {
+                let held = environment.test_condition(cexp)?;
+                Trace {
+                    effect: if held { (*eff).into() } else { SILENT },
+                    node: TraceNode::Atomic {
+                        effect: *eff,
+                        condition: cexp,
+                        held,
+                    },
+                }
+            }
+            Aggregate(effs) => {
+                let children = trace_all(effs, environment)?;
+                let effect = combine_non_strict(children.iter().map(|c| c.effect));
+                Trace {
+                    effect,
+                    node: TraceNode::Aggregate {
+                        deciding: with_effect(&children, effect),
+                        children,
+                    },
+                }
+            }
+            Disjoint(effs) => {
+                let children = trace_all(effs, environment)?;
+                let effect = combine_strict(children.iter().map(|c| c.effect));
+                Trace {
+                    effect,
+                    node: TraceNode::Disjoint {
+                        deciding: with_effect(&children, effect),
+                        children,
+                    },
+                }
+            }
+            Combined(algorithm, effs) => {
+                let children = trace_all(effs, environment)?;
+                let effects: Vec<ComputedEffect> = children.iter().map(|c| c.effect).collect();
+                Trace {
+                    effect: algorithm.combine(&effects),
+                    node: TraceNode::Combined {
+                        algorithm,
+                        deciding: algorithm.deciding(&effects),
+                        children,
+                    },
+                }
+            }
+            Prioritized(effs) => {
+                let children = effs
+                    .iter()
+                    .map(|(priority, e)| Ok((*priority, e.trace(environment)?)))
+                    .collect::<Result<Vec<_>, Env::Err>>()?;
+                let effect = combine_prioritized(children.iter().map(|(p, c)| (*p, c.effect)));
+                let highest = children
+                    .iter()
+                    .filter(|(_, c)| c.effect != SILENT)
+                    .map(|(p, _)| *p)
+                    .max();
+                let deciding = children
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (p, c))| {
+                        c.effect == effect && !matches!(highest, Some(h) if *p != h)
+                    })
+                    .map(|(i, _)| i)
+                    .collect();
+                Trace {
+                    effect,
+                    node: TraceNode::Prioritized { children, deciding },
+                }
+            }
+            Obligated(duties, eff) => {
+                let child = eff.trace(environment)?;
+                Trace {
+                    effect: child.effect,
+                    node: TraceNode::Obligated {
+                        duties,
+                        child: Box::new(child),
+                    },
+                }
+            }
+            Identified(id, eff) => {
+                let child = eff.trace(environment)?;
+                Trace {
+                    effect: child.effect,
+                    node: TraceNode::Identified {
+                        id,
+                        child: Box::new(child),
+                    },
+                }
+            }
+        };
+        Ok(trace)
+    }
+}
+
+/// Private helper. Resolve and record constituents.
+fn trace_all<'a, CExp, Env>(
+    effs: &'a [DependentEffect<CExp>],
+    environment: &Env,
+) -> Result<Vec<Trace<'a, CExp>>, Env::Err>
+where
+    Env: Environment<CExp = CExp>,
+{
+    effs.iter().map(|e| e.trace(environment)).collect()
+}
+
+/// Private helper. Indices of the traces that resolved to an effect.
+fn with_effect<CExp>(children: &[Trace<'_, CExp>], effect: ComputedEffect) -> Vec<usize> {
+    children
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.effect == effect)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+impl<'a, CExp> Trace<'a, CExp> {
+    /// Fold the nodes that contributed to the effect into a value, outermost
+    /// first.
+    ///
+    /// A node contributes if it is not silent and each combination above it
+    /// counts it among the children deciding the combination. A silent trace
+    /// has no contributing nodes.
+    pub fn collect_contributing<T, F>(&self, init: T, mut add: F) -> T
+    where
+        F: FnMut(&mut T, &TraceNode<'a, CExp>),
+    {
+        let mut acc = init;
+        self.visit_contributing(&mut |node| add(&mut acc, node));
+        acc
+    }
+
+    /// Private helper. Visit a node and its contributing children.
+    fn visit_contributing(&self, visit: &mut dyn FnMut(&TraceNode<'a, CExp>)) {
+        if self.effect == SILENT {
+            return;
+        }
+        visit(&self.node);
+        match &self.node {
+            TraceNode::Silent | TraceNode::Fixed(_) | TraceNode::Atomic { .. } => {}
+            TraceNode::Aggregate { children, deciding }
+            | TraceNode::Disjoint { children, deciding }
+            | TraceNode::Combined {
+                children, deciding, ..
+            } => deciding
+                .iter()
+                .for_each(|i| children[*i].visit_contributing(visit)),
+            TraceNode::Prioritized { children, deciding } => deciding
+                .iter()
+                .for_each(|i| children[*i].1.visit_contributing(visit)),
+            TraceNode::Obligated { child, .. } | TraceNode::Identified { child, .. } => {
+                child.visit_contributing(visit)
+            }
+        }
+    }
+
+    /// Render the trace as indented text, one node per line, describing
+    /// conditions with a function. Each line starts with the effect the node
+    /// resolved to and deciding children are marked with `*`.
+    pub fn render_with<F>(&self, describe: F) -> String
+    where
+        F: Fn(&CExp) -> String,
+    {
+        let mut out = String::new();
+        self.render_into(&mut out, &describe, 0, false, None);
+        out
+    }
+
+    /// Private helper. Render a node and its children.
+    fn render_into(
+        &self,
+        out: &mut String,
+        describe: &dyn Fn(&CExp) -> String,
+        depth: usize,
+        deciding: bool,
+        priority: Option<Priority>,
+    ) {
+        if depth > 0 {
+            out.push_str(&"  ".repeat(depth - 1));
+            out.push_str(if deciding { "* " } else { "  " });
+        }
+        out.push_str(effect_name(self.effect));
+        out.push(' ');
+        if let Some(priority) = priority {
+            out.push_str(&format!("priority {}: ", priority));
+        }
+        match &self.node {
+            TraceNode::Silent => out.push_str("silent\n"),
+            TraceNode::Fixed(eff) => out.push_str(&format!("fixed {:?}\n", eff)),
+            TraceNode::Atomic {
+                effect,
+                condition,
+                held,
+            } => out.push_str(&format!(
+                "{:?} if {}: {}\n",
+                effect,
+                describe(condition),
+                if *held { "held" } else { "did not hold" }
+            )),
+            TraceNode::Aggregate { children, deciding }
+            | TraceNode::Disjoint { children, deciding }
+            | TraceNode::Combined {
+                children, deciding, ..
+            } => {
+                match self.node {
+                    TraceNode::Aggregate { .. } => out.push_str("aggregate\n"),
+                    TraceNode::Disjoint { .. } => out.push_str("disjoint\n"),
+                    TraceNode::Combined { algorithm, .. } => {
+                        out.push_str(&format!("combined {:?}\n", algorithm))
+                    }
+                    _ => unreachable!(),
+                }
+                for (i, child) in children.iter().enumerate() {
+                    child.render_into(out, describe, depth + 1, deciding.contains(&i), None);
+                }
+            }
+            TraceNode::Prioritized { children, deciding } => {
+                out.push_str("prioritized\n");
+                for (i, (priority, child)) in children.iter().enumerate() {
+                    let deciding = deciding.contains(&i);
+                    child.render_into(out, describe, depth + 1, deciding, Some(*priority));
+                }
+            }
+            TraceNode::Obligated { duties, child } => {
+                out.push_str(&format!("obligated with {}\n", duties));
+                child.render_into(out, describe, depth + 1, true, None);
+            }
+            TraceNode::Identified { id, child } => {
+                out.push_str(&format!("statement {}\n", id));
+                child.render_into(out, describe, depth + 1, true, None);
+            }
+        }
+    }
+}
+
+/// Private helper. Name of a computed effect.
+fn effect_name(effect: ComputedEffect) -> &'static str {
+    match effect {
+        ALLOW => "ALLOW",
+        DENY => "DENY",
+        _ => "SILENT",
+    }
+}
+
+impl<CExp> fmt::Display for Trace<'_, CExp>
+where
+    CExp: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render_with(|c| c.to_string()))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+
+    use super::*;
+    use crate::condition::*;
+
+    /// Environment in which only the condition equal to its value holds and
+    /// testing the condition `0` fails. Shared with the tests of modules
+    /// reading traces.
+    pub(crate) struct Equals(pub(crate) u32);
+
+    impl Environment for Equals {
+        type Err = ();
+        type CExp = u32;
+
+        fn test_condition(&self, exp: &Self::CExp) -> Result<bool, Self::Err> {
+            match exp {
+                0 => Err(()),
+                exp => Ok(*exp == self.0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_trace_leaves() {
+        use DependentEffect::*;
+
+        let effect = Silent;
+        let actual = effect.resolve_explained(&Equals(1));
+        assert_eq!(
+            actual,
+            Ok((
+                SILENT,
+                Trace {
+                    effect: SILENT,
+                    node: TraceNode::Silent
+                }
+            ))
+        );
+
+        let effect = Atomic(Effect::ALLOW, 2);
+        let (resolved, trace) = effect.resolve_explained(&Equals(1)).unwrap();
+        assert_eq!(resolved, SILENT);
+        assert_eq!(
+            trace.node,
+            TraceNode::Atomic {
+                effect: Effect::ALLOW,
+                condition: &2,
+                held: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_deciding_children() {
+        use DependentEffect::*;
+        let deciding = |effect: DependentEffect<u32>| match effect.resolve_explained(&Equals(1)) {
+            Ok((_, trace)) => match trace.node {
+                TraceNode::Aggregate { deciding, .. } | TraceNode::Disjoint { deciding, .. } => {
+                    deciding
+                }
+                _ => unreachable!(),
+            },
+            Err(_) => unreachable!(),
+        };
+
+        let effects = vec![
+            Atomic(Effect::ALLOW, 1),
+            Silent,
+            Atomic(Effect::DENY, 1),
+            Fixed(Effect::ALLOW),
+            Atomic(Effect::DENY, 2),
+        ];
+        assert_eq!(deciding(Aggregate(effects.clone())), vec![2]);
+        assert_eq!(deciding(Disjoint(effects)), vec![1, 4]);
+
+        let effects = vec![Atomic(Effect::ALLOW, 1), Silent, Fixed(Effect::ALLOW)];
+        assert_eq!(deciding(Aggregate(effects)), vec![0, 2]);
+
+        let effects = vec![Atomic(Effect::ALLOW, 1), Fixed(Effect::DENY)];
+        assert_eq!(deciding(Disjoint(effects)), vec![1]);
+
+        assert!(deciding(Aggregate(vec![])).is_empty());
+        assert_eq!(deciding(Aggregate(vec![Silent, Silent])), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_trace_combined() {
+        use DependentEffect::*;
+        let effect = Combined(
+            Algorithm::FirstApplicable,
+            vec![
+                Atomic(Effect::DENY, 2),
+                Atomic(Effect::ALLOW, 1),
+                Fixed(Effect::DENY),
+            ],
+        );
+
+        let (resolved, trace) = effect.resolve_explained(&Equals(1)).unwrap();
+
+        assert_eq!(resolved, ALLOW);
+        let expected = "\
+ALLOW combined FirstApplicable
+  SILENT DENY if 2: did not hold
+* ALLOW ALLOW if 1: held
+  DENY fixed DENY
+";
+        assert_eq!(trace.to_string(), expected);
+    }
+
+    #[test]
+    fn test_trace_prioritized() {
+        use DependentEffect::*;
+        let effect = Prioritized(vec![
+            (1, Fixed(Effect::DENY)),
+            (5, Atomic(Effect::ALLOW, 1)),
+            (5, Atomic(Effect::DENY, 2)),
+            (5, Aggregate(vec![Fixed(Effect::ALLOW)])),
+        ]);
+
+        let (resolved, trace) = effect.resolve_explained(&Equals(1)).unwrap();
+
+        assert_eq!(resolved, ALLOW);
+        let expected = "\
+ALLOW prioritized
+  DENY priority 1: fixed DENY
+* ALLOW priority 5: ALLOW if 1: held
+  SILENT priority 5: DENY if 2: did not hold
+* ALLOW priority 5: aggregate
+  * ALLOW fixed ALLOW
+";
+        assert_eq!(trace.to_string(), expected);
+
+        let (resolved, trace) = effect.resolve_explained(&Equals(2)).unwrap();
+        assert_eq!(resolved, DENY);
+        match trace.node {
+            TraceNode::Prioritized { deciding, .. } => assert_eq!(deciding, vec![2]),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_trace_obligated() {
+        use crate::obligation::*;
+        use DependentEffect::*;
+        let duties = Duties::new()
+            .with_obligation(Duty::new("audit").with("stream", "security"))
+            .with_advice(Duty::new("notify"));
+        let effect = Aggregate(vec![
+            Obligated(duties, Box::new(Atomic(Effect::ALLOW, 1))),
+            Fixed(Effect::ALLOW),
+        ]);
+
+        let (_, trace) = effect.resolve_explained(&Equals(1)).unwrap();
+
+        let expected = "\
+ALLOW aggregate
+* ALLOW obligated with obligations audit(stream=security); advice notify
+  * ALLOW ALLOW if 1: held
+* ALLOW fixed ALLOW
+";
+        assert_eq!(trace.to_string(), expected);
+    }
+
+    #[test]
+    fn test_trace_identified() {
+        use DependentEffect::*;
+        let effect = Disjoint(vec![
+            Identified("app".to_string(), Box::new(Atomic(Effect::ALLOW, 1))),
+            Identified("user".to_string(), Box::new(Fixed(Effect::DENY))),
+        ]);
+
+        let (_, trace) = effect.resolve_explained(&Equals(1)).unwrap();
+
+        let expected = "\
+DENY disjoint
+  ALLOW statement app
+  * ALLOW ALLOW if 1: held
+* DENY statement user
+  * DENY fixed DENY
+";
+        assert_eq!(trace.to_string(), expected);
+    }
+
+    #[test]
+    fn test_contributing() {
+        use DependentEffect::*;
+        let effect = Aggregate(vec![
+            Identified("read".to_string(), Box::new(Atomic(Effect::ALLOW, 1))),
+            Identified("all".to_string(), Box::new(Fixed(Effect::ALLOW))),
+            Identified("deny".to_string(), Box::new(Atomic(Effect::DENY, 2))),
+        ]);
+        let visited = |value: u32| {
+            let (_, trace) = effect.resolve_explained(&Equals(value)).unwrap();
+            trace.collect_contributing(Vec::new(), |nodes, node| {
+                nodes.push(match node {
+                    TraceNode::Identified { id, .. } => id.to_string(),
+                    TraceNode::Aggregate { .. } => "aggregate".to_string(),
+                    TraceNode::Atomic { condition, .. } => condition.to_string(),
+                    TraceNode::Fixed(_) => "fixed".to_string(),
+                    _ => unreachable!(),
+                })
+            })
+        };
+
+        assert_eq!(visited(1), vec!["aggregate", "read", "1", "all", "fixed"]);
+        assert_eq!(visited(2), vec!["aggregate", "deny", "2"]);
+
+        let effect = Aggregate(vec![Atomic(Effect::ALLOW, 1)]);
+        let (_, trace) = effect.resolve_explained(&Equals(2)).unwrap();
+        trace.collect_contributing((), |_, _| panic!("silent trace has no contributing nodes"));
+    }
+
+    #[test]
+    fn test_trace_agrees_with_resolve() {
+        use DependentEffect::*;
+        let effect = Disjoint(vec![
+            Aggregate(vec![Atomic(Effect::ALLOW, 1), Atomic(Effect::DENY, 2)]),
+            Aggregate(vec![Atomic(Effect::ALLOW, 3), Fixed(Effect::ALLOW)]),
+        ]);
+
+        for value in 1..5 {
+            let (actual, trace) = effect.resolve_explained(&Equals(value)).unwrap();
+
+            let expected = effect.resolve(&Equals(value)).unwrap();
+            assert_eq!(actual, expected);
+            assert_eq!(trace.effect, expected);
+        }
+    }
+
+    #[test]
+    fn test_trace_error() {
+        use DependentEffect::*;
+        let effect = Aggregate(vec![Fixed(Effect::DENY), Atomic(Effect::ALLOW, 0)]);
+
+        assert_eq!(effect.resolve_explained(&Equals(1)), Err(()));
+    }
+
+    #[test]
+    fn test_render() {
+        use DependentEffect::*;
+        let effect = Disjoint(vec![
+            Aggregate(vec![
+                Atomic(Effect::ALLOW, 1),
+                Atomic(Effect::DENY, 2),
+                Silent,
+            ]),
+            Fixed(Effect::ALLOW),
+        ]);
+
+        let (_, trace) = effect.resolve_explained(&Equals(1)).unwrap();
+
+        let expected = "\
+ALLOW disjoint
+* ALLOW aggregate
+  * ALLOW ALLOW if 1: held
+    SILENT DENY if 2: did not hold
+    SILENT silent
+* ALLOW fixed ALLOW
+";
+        assert_eq!(trace.to_string(), expected);
+    }
+
+    #[test]
+    fn test_render_conditions() {
+        let effect = DependentEffect::Aggregate(vec![
+            DependentEffect::Atomic(Effect::ALLOW, "user.role == \"admin\"".parse().unwrap()),
+            DependentEffect::Atomic(Effect::DENY, "exists(user.suspended)".parse().unwrap()),
+        ]);
+        let env = AttributeEnvironment::new()
+            .with("user.role", "admin")
+            .with("user.suspended", true);
+
+        let (resolved, trace) = effect.resolve_explained(&env).unwrap();
+
+        assert_eq!(resolved, DENY);
+        let expected = "\
+DENY aggregate
+  ALLOW ALLOW if user.role == \"admin\": held
+* DENY DENY if exists(user.suspended): held
+";
+        assert_eq!(trace.to_string(), expected);
+    }
+}
@@ -0,0 +1,363 @@
+//! Glob-style patterns for matching single strings.
+//!
+//! Supported syntax:
+//!
+//! * `?` matches exactly one character
+//! * `*` matches any run of characters, including the empty run
+//! * `[abc]`, `[a-z]` match one character from a class, `[!abc]` negates the class
+//! * `\c` matches the character `c` literally, e.g. `\*` or `\[`
+
+use std::fmt;
+
+/// Error produced when a glob pattern cannot be compiled.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum GlobError {
+    /// A character class was opened at the given character offset but never closed.
+    UnterminatedClass(usize),
+    /// The pattern ends with an escape character.
+    TrailingEscape,
+    /// A class range at the given character offset has its bounds reversed e.g. `[z-a]`.
+    InvalidRange(usize),
+}
+
+impl fmt::Display for GlobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use GlobError::*;
+        match self {
+            UnterminatedClass(at) => write!(f, "unterminated character class at offset {}", at),
+            TrailingEscape => write!(f, "pattern ends with an escape character"),
+            InvalidRange(at) => write!(f, "invalid character range at offset {}", at),
+        }
+    }
+}
+
+impl std::error::Error for GlobError {}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Token {
+    Literal(char),
+    AnyChar,
+    AnyRun,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Token {
+    /// Test a single character against a single-character token.
+    fn accepts(&self, c: char) -> bool {
+        use Token::*;
+        match self {
+            Literal(l) => *l == c,
+            AnyChar => true,
+            AnyRun => false,
+            Class { negated, ranges } => {
+                ranges.iter().any(|(lo, hi)| *lo <= c && c <= *hi) != *negated
+            }
+        }
+    }
+}
+
+/// A compiled glob pattern.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Glob(Vec<Token>);
+
+impl Glob {
+    /// Compile a pattern.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use authorization_core::glob::*;
+    ///
+    /// let glob = Glob::new("report-*.pdf").unwrap();
+    ///
+    /// assert!(glob.test("report-2020.pdf"));
+    /// assert!(!glob.test("report-2020.txt"));
+    /// assert_eq!(Glob::new("[a-"), Err(GlobError::UnterminatedClass(0)));
+    /// ```
+    pub fn new(pattern: &str) -> Result<Self, GlobError> {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '?' => tokens.push(Token::AnyChar),
+                '*' => {
+                    // consecutive stars are equivalent to a single one
+                    if tokens.last() != Some(&Token::AnyRun) {
+                        tokens.push(Token::AnyRun);
+                    }
+                }
+                '\\' => {
+                    i += 1;
+                    let c = *chars.get(i).ok_or(GlobError::TrailingEscape)?;
+                    tokens.push(Token::Literal(c));
+                }
+                '[' => {
+                    let (token, next) = Self::parse_class(&chars, i)?;
+                    tokens.push(token);
+                    i = next;
+                    continue;
+                }
+                c => tokens.push(Token::Literal(c)),
+            }
+            i += 1;
+        }
+        Ok(Glob(tokens))
+    }
+
+    /// Parse a character class starting at `start` (the opening bracket). Returns
+    /// the class token and the offset following the closing bracket.
+    fn parse_class(chars: &[char], start: usize) -> Result<(Token, usize), GlobError> {
+        let mut i = start + 1;
+        let negated = chars.get(i) == Some(&'!');
+        if negated {
+            i += 1;
+        }
+        let mut ranges = Vec::new();
+        // a close bracket at the start of the class is taken literally
+        if chars.get(i) == Some(&']') {
+            ranges.push((']', ']'));
+            i += 1;
+        }
+        loop {
+            let at = i;
+            let lo = match chars.get(i) {
+                None => return Err(GlobError::UnterminatedClass(start)),
+                Some(']') => return Ok((Token::Class { negated, ranges }, i + 1)),
+                Some(_) => Self::class_char(chars, &mut i, start)?,
+            };
+            let hi = match (chars.get(i), chars.get(i + 1)) {
+                (Some('-'), Some(c)) if *c != ']' => {
+                    i += 1;
+                    Self::class_char(chars, &mut i, start)?
+                }
+                _ => lo,
+            };
+            if hi < lo {
+                return Err(GlobError::InvalidRange(at));
+            }
+            ranges.push((lo, hi));
+        }
+    }
+
+    /// Read a possibly escaped character inside a class, advancing the offset past it.
+    fn class_char(chars: &[char], i: &mut usize, start: usize) -> Result<char, GlobError> {
+        if chars.get(*i) == Some(&'\\') {
+            *i += 1;
+        }
+        let c = *chars.get(*i).ok_or(GlobError::UnterminatedClass(start))?;
+        *i += 1;
+        Ok(c)
+    }
+
+    /// Determine if a string matches the pattern.
+    ///
+    /// Matching never backtracks more than once per star so it runs in
+    /// `O(pattern * input)` time in the worst case.
+    pub fn test(&self, target: &str) -> bool {
+        let text: Vec<char> = target.chars().collect();
+        let pat = &self.0;
+        let (mut p, mut t) = (0, 0);
+        // position of the last star seen and the text offset it currently absorbs to
+        let mut star: Option<(usize, usize)> = None;
+
+        while t < text.len() {
+            match pat.get(p) {
+                Some(Token::AnyRun) => {
+                    star = Some((p, t));
+                    p += 1;
+                }
+                Some(tok) if tok.accepts(text[t]) => {
+                    p += 1;
+                    t += 1;
+                }
+                _ => match star {
+                    Some((sp, st)) => {
+                        p = sp + 1;
+                        t = st + 1;
+                        star = Some((sp, st + 1));
+                    }
+                    None => return false,
+                },
+            }
+        }
+        pat[p..].iter().all(|tok| *tok == Token::AnyRun)
+    }
+
+    /// Determine if the pattern matches every string, i.e. is `*`.
+    pub fn is_any(&self) -> bool {
+        self.0 == [Token::AnyRun]
+    }
+
+    /// If the pattern contains no wildcards, i.e. matches exactly one string,
+    /// produce that string.
+    pub fn as_literal(&self) -> Option<String> {
+        self.0
+            .iter()
+            .map(|t| match t {
+                Token::Literal(c) => Some(*c),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Glob {
+    /// Writes the canonical form of the pattern. The canonical form compiles
+    /// to an equal `Glob`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn class_char(f: &mut fmt::Formatter<'_>, c: char) -> fmt::Result {
+            if matches!(c, '\\' | ']' | '-' | '!') {
+                write!(f, "\\")?;
+            }
+            write!(f, "{}", c)
+        }
+        for token in &self.0 {
+            match token {
+                Token::Literal(c) => {
+                    if matches!(c, '\\' | '*' | '?' | '[') {
+                        write!(f, "\\")?;
+                    }
+                    write!(f, "{}", c)?;
+                }
+                Token::AnyChar => write!(f, "?")?,
+                Token::AnyRun => write!(f, "*")?,
+                Token::Class { negated, ranges } => {
+                    write!(f, "[")?;
+                    if *negated {
+                        write!(f, "!")?;
+                    }
+                    for (lo, hi) in ranges {
+                        class_char(f, *lo)?;
+                        if lo != hi {
+                            write!(f, "-")?;
+                            class_char(f, *hi)?;
+                        }
+                    }
+                    write!(f, "]")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Glob {
+    type Err = GlobError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Glob::new(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn glob(p: &str) -> Glob {
+        Glob::new(p).unwrap()
+    }
+
+    #[test]
+    fn test_literal() {
+        assert!(glob("abc").test("abc"));
+        assert!(!glob("abc").test("abcd"));
+        assert!(!glob("abc").test("ab"));
+        assert!(glob("").test(""));
+        assert!(!glob("").test("a"));
+    }
+
+    #[test]
+    fn test_any_char() {
+        assert!(glob("v?").test("v1"));
+        assert!(!glob("v?").test("v"));
+        assert!(!glob("v?").test("v12"));
+    }
+
+    #[test]
+    fn test_any_run() {
+        assert!(glob("report-*").test("report-"));
+        assert!(glob("report-*").test("report-2020"));
+        assert!(glob("*.pdf").test("a.pdf"));
+        assert!(!glob("*.pdf").test("a.pdf.txt"));
+        assert!(glob("a*b*c").test("aXXbYYbc"));
+        assert!(!glob("a*b*c").test("aXXbYY"));
+        assert!(glob("*").test(""));
+        assert!(glob("**").test("anything"));
+    }
+
+    #[test]
+    fn test_class() {
+        assert!(glob("[abc]").test("b"));
+        assert!(!glob("[abc]").test("d"));
+        assert!(glob("[a-z]x").test("qx"));
+        assert!(!glob("[a-z]x").test("Qx"));
+        assert!(glob("[!a-z]").test("Q"));
+        assert!(!glob("[!a-z]").test("q"));
+        assert!(glob("[]]").test("]"));
+        assert!(glob("[a-]").test("-"));
+        assert!(glob("[\\]]").test("]"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert!(glob("\\*").test("*"));
+        assert!(!glob("\\*").test("a"));
+        assert!(glob("a\\?").test("a?"));
+        assert!(!glob("a\\?").test("ab"));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(Glob::new("ab["), Err(GlobError::UnterminatedClass(2)));
+        assert_eq!(Glob::new("[!"), Err(GlobError::UnterminatedClass(0)));
+        assert_eq!(Glob::new("ab\\"), Err(GlobError::TrailingEscape));
+        assert_eq!(Glob::new("[z-a]"), Err(GlobError::InvalidRange(1)));
+    }
+
+    #[test]
+    fn test_pathological_input_is_fast() {
+        let pattern = glob("a*a*a*a*a*a*a*a*b");
+        let text = "a".repeat(10_000);
+        assert!(!pattern.test(&text));
+    }
+
+    #[test]
+    fn test_as_literal() {
+        assert_eq!(glob("abc").as_literal(), Some("abc".into()));
+        assert_eq!(glob("a\\*").as_literal(), Some("a*".into()));
+        assert_eq!(glob("").as_literal(), Some("".into()));
+        assert_eq!(glob("a*").as_literal(), None);
+        assert_eq!(glob("a?").as_literal(), None);
+        assert_eq!(glob("[a]").as_literal(), None);
+    }
+
+    #[test]
+    fn test_is_any() {
+        assert!(glob("*").is_any());
+        assert!(glob("***").is_any());
+        assert!(!glob("").is_any());
+        assert!(!glob("*a").is_any());
+        assert!(!glob("\\*").is_any());
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for p in &[
+            "abc",
+            "a*b?c",
+            "[!a-z]x",
+            "\\*\\?\\[",
+            "[\\]\\-]",
+            "[a-]",
+            "*.pdf",
+        ] {
+            let g = glob(p);
+            assert_eq!(glob(&g.to_string()), g);
+        }
+    }
+}
@@ -0,0 +1,400 @@
+//! Implication between actions.
+//!
+//! An implication `a => b` declares that permission for action `a` includes
+//! permission for action `b`, e.g. `admin => write` and `write => read`.
+//! Implication is transitive so `admin` then also implies `read`.
+
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+
+use super::matcher::*;
+
+/// Error produced when declared implications form a cycle.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CycleError<A>(
+    /// The actions on the cycle in order. The last action implies the first.
+    pub Vec<A>,
+);
+
+impl<A: fmt::Debug> fmt::Display for CycleError<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "action implications form a cycle: {:?}", self.0)
+    }
+}
+
+impl<A: fmt::Debug> std::error::Error for CycleError<A> {}
+
+/// Acyclic, transitively closed registry of action implications.
+///
+/// # Examples
+///
+/// ```
+/// use authorization_core::implication::*;
+///
+/// let implications = Implications::new(vec![("admin", "write"), ("write", "read")]).unwrap();
+///
+/// assert!(implications.implies(&"admin", &"read"));
+/// assert!(!implications.implies(&"read", &"write"));
+/// assert_eq!(implications.implied_by(&"admin"), vec![&"write", &"read"]);
+///
+/// let cyclic = Implications::new(vec![("a", "b"), ("b", "a")]);
+/// assert_eq!(cyclic.unwrap_err(), CycleError(vec!["a", "b"]));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Implications<A> {
+    /// Every action transitively implied by the key
+    implied: HashMap<A, Closure<A>>,
+    /// Every action that transitively implies the key
+    implying: HashMap<A, Closure<A>>,
+}
+
+/// Actions reachable from an action, in depth-first order and as a set for
+/// constant time membership tests.
+#[derive(Debug, Clone)]
+struct Closure<A> {
+    order: Vec<A>,
+    members: HashSet<A>,
+}
+
+impl<A> Closure<A>
+where
+    A: Eq + Hash + Clone,
+{
+    fn new() -> Self {
+        Closure {
+            order: Vec::new(),
+            members: HashSet::new(),
+        }
+    }
+
+    /// Add an action unless present.
+    fn insert(&mut self, action: &A) {
+        if self.members.insert(action.clone()) {
+            self.order.push(action.clone());
+        }
+    }
+}
+
+impl<A> Implications<A>
+where
+    A: Eq + Hash + Clone,
+{
+    /// Build a registry from `(a, b)` pairs each declaring that `a` implies `b`.
+    /// Fails if the declarations form a cycle, including an action implying itself.
+    pub fn new<I>(implications: I) -> Result<Self, CycleError<A>>
+    where
+        I: IntoIterator<Item = (A, A)>,
+    {
+        let mut direct: HashMap<A, Vec<A>> = HashMap::new();
+        let mut reverse: HashMap<A, Vec<A>> = HashMap::new();
+        let mut actions = Vec::new();
+        for (a, b) in implications {
+            for x in [&a, &b] {
+                if !direct.contains_key(x) {
+                    direct.insert(x.clone(), Vec::new());
+                    reverse.insert(x.clone(), Vec::new());
+                    actions.push(x.clone());
+                }
+            }
+            if !direct[&a].contains(&b) {
+                direct.get_mut(&a).unwrap().push(b.clone());
+                reverse.get_mut(&b).unwrap().push(a);
+            }
+        }
+
+        // successors come before the actions implying them
+        let order = topological_order(&actions, &direct)?;
+
+        Ok(Implications {
+            implied: close(order.iter().copied(), &direct),
+            implying: close(order.iter().rev().copied(), &reverse),
+        })
+    }
+
+    /// Enumerate every action transitively implied by an action, not including
+    /// the action itself.
+    pub fn implied_by(&self, action: &A) -> Vec<&A> {
+        self.implied
+            .get(action)
+            .map(|c| c.order.iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// Enumerate every action that transitively implies an action, not including
+    /// the action itself.
+    pub fn implying(&self, action: &A) -> Vec<&A> {
+        self.implying
+            .get(action)
+            .map(|c| c.order.iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// Determine if permission for `a` includes permission for `b`. Every
+    /// action implies itself.
+    pub fn implies(&self, a: &A, b: &A) -> bool {
+        a == b || self.implied.get(a).is_some_and(|c| c.members.contains(b))
+    }
+}
+
+/// Private helper. Depth-first search ordering the actions so that each comes
+/// after every action it has an edge to, or reporting the first cycle found.
+/// The search is iterative so long chains of implications do not exhaust the
+/// call stack.
+fn topological_order<'a, A>(
+    actions: &'a [A],
+    edges: &'a HashMap<A, Vec<A>>,
+) -> Result<Vec<&'a A>, CycleError<A>>
+where
+    A: Eq + Hash + Clone,
+{
+    let mut done: HashSet<&A> = HashSet::new();
+    let mut order: Vec<&A> = Vec::with_capacity(actions.len());
+    for root in actions {
+        if done.contains(root) {
+            continue;
+        }
+        // actions on the current path with the index of the next edge to follow
+        let mut stack: Vec<(&A, usize)> = vec![(root, 0)];
+        let mut on_stack: HashMap<&A, usize> = HashMap::from([(root, 0)]);
+        while let Some((action, edge)) = stack.last_mut() {
+            let action: &A = action;
+            match edges[action].get(*edge) {
+                Some(next) => {
+                    *edge += 1;
+                    if let Some(start) = on_stack.get(next) {
+                        return Err(CycleError(
+                            stack[*start..].iter().map(|(a, _)| (*a).clone()).collect(),
+                        ));
+                    }
+                    if !done.contains(next) {
+                        on_stack.insert(next, stack.len());
+                        stack.push((next, 0));
+                    }
+                }
+                None => {
+                    stack.pop();
+                    on_stack.remove(action);
+                    done.insert(action);
+                    order.push(action);
+                }
+            }
+        }
+    }
+    Ok(order)
+}
+
+/// Private helper. Transitive closure of an acyclic relation, in depth-first
+/// order. The actions must be ordered so that each comes after every action it
+/// has an edge to; each closure then extends the closures of its successors
+/// instead of searching again from the action.
+fn close<'a, A, I>(order: I, edges: &HashMap<A, Vec<A>>) -> HashMap<A, Closure<A>>
+where
+    A: Eq + Hash + Clone + 'a,
+    I: IntoIterator<Item = &'a A>,
+{
+    let mut closures: HashMap<A, Closure<A>> = HashMap::with_capacity(edges.len());
+    for action in order {
+        let mut closure = Closure::new();
+        for next in &edges[action] {
+            closure.insert(next);
+            closures[next].order.iter().for_each(|a| closure.insert(a));
+        }
+        closures.insert(action.clone(), closure);
+    }
+    closures
+}
+
+/// Action matcher that also matches any action implied by an action it matches.
+/// A policy granting `write` thus applies to a request for `read` when `write`
+/// implies `read`.
+///
+/// The registry can be held by value or shared e.g. as `&Implications<_>` or
+/// `Arc<Implications<_>>`.
+///
+/// # Examples
+///
+/// ```
+/// use authorization_core::action::*;
+/// use authorization_core::implication::*;
+/// use authorization_core::matcher::*;
+///
+/// let implications = Implications::new(vec![
+///     (StringAction::from("write"), StringAction::from("read")),
+/// ]).unwrap();
+/// let matcher = ImplyingMatcher::new(StringAction::from("write"), &implications);
+///
+/// assert!(matcher.test(&"read".into()));
+/// assert!(matcher.test(&"write".into()));
+/// assert!(!matcher.test(&"admin".into()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ImplyingMatcher<M, R> {
+    matcher: M,
+    implications: R,
+}
+
+impl<M, R> ImplyingMatcher<M, R> {
+    pub fn new(matcher: M, implications: R) -> Self {
+        ImplyingMatcher {
+            matcher,
+            implications,
+        }
+    }
+}
+
+impl<A, M, R> Matcher for ImplyingMatcher<M, R>
+where
+    A: Eq + Hash + Clone,
+    M: Matcher<Target = A>,
+    R: Borrow<Implications<A>>,
+{
+    type Target = A;
+
+    fn test(&self, target: &Self::Target) -> bool {
+        self.matcher.test(target)
+            || self
+                .implications
+                .borrow()
+                .implying(target)
+                .into_iter()
+                .any(|a| self.matcher.test(a))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::action::*;
+
+    fn lattice() -> Implications<&'static str> {
+        Implications::new(vec![
+            ("admin", "write"),
+            ("write", "read"),
+            ("admin", "delete"),
+            ("owner", "admin"),
+            ("write", "list"),
+            ("read", "list"),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_implied_by() {
+        let implications = lattice();
+
+        assert_eq!(
+            implications.implied_by(&"owner"),
+            vec![&"admin", &"write", &"read", &"list", &"delete"]
+        );
+        assert_eq!(implications.implied_by(&"read"), vec![&"list"]);
+        assert!(implications.implied_by(&"list").is_empty());
+        assert!(implications.implied_by(&"unknown").is_empty());
+    }
+
+    #[test]
+    fn test_implying() {
+        let implications = lattice();
+
+        assert_eq!(
+            implications.implying(&"read"),
+            vec![&"write", &"admin", &"owner"]
+        );
+        assert!(implications.implying(&"owner").is_empty());
+    }
+
+    #[test]
+    fn test_implies() {
+        let implications = lattice();
+
+        assert!(implications.implies(&"owner", &"list"));
+        assert!(implications.implies(&"read", &"read"));
+        assert!(implications.implies(&"unknown", &"unknown"));
+        assert!(!implications.implies(&"read", &"write"));
+        assert!(!implications.implies(&"delete", &"read"));
+    }
+
+    #[test]
+    fn test_cycle() {
+        let actual = Implications::new(vec![("a", "b"), ("b", "c"), ("c", "d"), ("d", "b")]);
+        assert_eq!(actual.unwrap_err(), CycleError(vec!["b", "c", "d"]));
+
+        let actual = Implications::new(vec![("a", "a")]);
+        assert_eq!(actual.unwrap_err(), CycleError(vec!["a"]));
+    }
+
+    #[test]
+    fn test_long_chain_cycle() {
+        let n = 200_000u32;
+        let chain = (0..n).map(|i| (i, (i + 1) % n));
+
+        let actual = Implications::new(chain);
+
+        assert_eq!(actual.unwrap_err(), CycleError((0..n).collect()));
+    }
+
+    #[test]
+    fn test_long_chain() {
+        let n = 2_000u32;
+        let implications = Implications::new((1..n).map(|i| (i - 1, i))).unwrap();
+
+        assert!(implications.implies(&0, &(n - 1)));
+        assert!(!implications.implies(&(n - 1), &0));
+        assert_eq!(
+            implications.implied_by(&0),
+            (1..n).collect::<Vec<_>>().iter().collect::<Vec<_>>()
+        );
+        assert_eq!(implications.implying(&(n - 1)).len(), n as usize - 1);
+    }
+
+    #[test]
+    fn test_diamond_is_not_a_cycle() {
+        let actual = Implications::new(vec![("a", "b"), ("a", "c"), ("b", "d"), ("c", "d")]);
+
+        assert_eq!(actual.unwrap().implied_by(&"a"), vec![&"b", &"d", &"c"]);
+    }
+
+    #[test]
+    fn test_implying_matcher() {
+        let implications = Implications::new(vec![
+            (StrAction("admin"), StrAction("write")),
+            (StrAction("admin"), StrAction("delete")),
+            (StrAction("write"), StrAction("read")),
+            (StrAction("read"), StrAction("list")),
+        ])
+        .unwrap();
+        let implications = Arc::new(implications);
+        let write = ImplyingMatcher::new(StrAction("write"), implications.clone());
+        let list = ImplyingMatcher::new(StrAction("list"), implications);
+
+        assert!(write.test(&"write".into()));
+        assert!(write.test(&"read".into()));
+        assert!(write.test(&"list".into()));
+        assert!(!write.test(&"admin".into()));
+        assert!(!write.test(&"delete".into()));
+
+        assert!(list.test(&"list".into()));
+        assert!(!list.test(&"read".into()));
+    }
+
+    #[test]
+    fn test_implying_matcher_with_qualified_actions() {
+        let action = |s: &str| -> QualifiedAction { s.parse().unwrap() };
+        let implications = Implications::new(vec![(
+            action("storage:objects:write"),
+            action("storage:objects:read"),
+        )])
+        .unwrap();
+        let matcher = ImplyingMatcher::new(
+            "storage:*:write".parse::<QualifiedActionMatcher>().unwrap(),
+            &implications,
+        );
+
+        assert!(matcher.test(&action("storage:objects:read")));
+        assert!(!matcher.test(&action("storage:buckets:read")));
+    }
+}
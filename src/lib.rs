@@ -1,9 +1,22 @@
 pub mod action;
+pub mod async_resolve;
+pub mod condition;
+pub mod condition_syntax;
 pub mod dependent_effect;
 pub mod effect;
 pub mod environment;
+pub mod explain;
+pub mod glob;
+pub mod implication;
 pub mod matcher;
+pub mod metadata;
+pub mod network;
+pub mod obligation;
+pub mod partial;
 pub mod path;
+pub mod path_index;
 pub mod policy;
 pub mod policy_template;
 pub mod resource;
+pub mod simplify;
+pub mod time;
@@ -5,6 +5,13 @@
 //! think it's an equivalance class but maybe something
 //! along those lines.
 
+use std::fmt;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use super::glob::GlobError;
+
 /// Basic matcher trait. Represents a class of values
 /// for which inclusion can be tested.
 pub trait Matcher {
@@ -15,6 +22,108 @@ pub trait Matcher {
     fn test(&self, target: &Self::Target) -> bool;
 }
 
+impl<M> Matcher for &M
+where
+    M: Matcher + ?Sized,
+{
+    type Target = M::Target;
+
+    fn test(&self, target: &Self::Target) -> bool {
+        (**self).test(target)
+    }
+}
+
+impl<M> Matcher for Box<M>
+where
+    M: Matcher + ?Sized,
+{
+    type Target = M::Target;
+
+    fn test(&self, target: &Self::Target) -> bool {
+        (**self).test(target)
+    }
+}
+
+impl<M> Matcher for Rc<M>
+where
+    M: Matcher + ?Sized,
+{
+    type Target = M::Target;
+
+    fn test(&self, target: &Self::Target) -> bool {
+        (**self).test(target)
+    }
+}
+
+impl<M> Matcher for Arc<M>
+where
+    M: Matcher + ?Sized,
+{
+    type Target = M::Target;
+
+    fn test(&self, target: &Self::Target) -> bool {
+        (**self).test(target)
+    }
+}
+
+/// Matcher chosen at runtime.
+pub type DynMatcher<T> = Box<dyn Matcher<Target = T>>;
+
+/// Matcher defined by a predicate function.
+///
+/// # Examples
+///
+/// ```
+/// use authorization_core::matcher::*;
+///
+/// let even = FnMatcher::new(|n: &u32| n % 2 == 0);
+///
+/// assert!(even.test(&2));
+/// assert!(!even.test(&3));
+/// ```
+pub struct FnMatcher<F, T> {
+    f: F,
+    target: PhantomData<fn(&T)>,
+}
+
+impl<F, T> FnMatcher<F, T>
+where
+    F: Fn(&T) -> bool,
+{
+    pub fn new(f: F) -> Self {
+        FnMatcher {
+            f,
+            target: PhantomData,
+        }
+    }
+}
+
+impl<F, T> Matcher for FnMatcher<F, T>
+where
+    F: Fn(&T) -> bool,
+{
+    type Target = T;
+
+    fn test(&self, target: &Self::Target) -> bool {
+        (self.f)(target)
+    }
+}
+
+impl<F: Clone, T> Clone for FnMatcher<F, T> {
+    fn clone(&self) -> Self {
+        FnMatcher {
+            f: self.f.clone(),
+            target: PhantomData,
+        }
+    }
+}
+
+impl<F, T> fmt::Debug for FnMatcher<F, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FnMatcher")
+    }
+}
+
 /// Convenience methods for matchers. Non-trivial matchers should implement
 /// this.
 pub trait ExtendedMatcher {
@@ -30,9 +139,387 @@ pub trait ExtendedMatcher {
     fn match_none() -> Self;
 }
 
+/// Matchers that can also be constructed from a glob pattern. See
+/// `authorization_core::glob` for the pattern syntax.
+pub trait GlobMatcher: ExtendedMatcher + Sized {
+    /// Match any target conforming to a pattern
+    fn match_glob(pattern: &str) -> Result<Self, GlobError>;
+}
+
+/// Matches targets matched by both constituents.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct And<A, B>(pub A, pub B);
+
+impl<T, A, B> Matcher for And<A, B>
+where
+    A: Matcher<Target = T>,
+    B: Matcher<Target = T>,
+{
+    type Target = T;
+
+    fn test(&self, target: &Self::Target) -> bool {
+        self.0.test(target) && self.1.test(target)
+    }
+}
+
+impl<T, A, B> ExtendedMatcher for And<A, B>
+where
+    T: Clone,
+    A: ExtendedMatcher<Target = T>,
+    B: ExtendedMatcher<Target = T>,
+{
+    type Target = T;
+
+    fn match_only<X: Into<Self::Target>>(target: X) -> Self {
+        let target = target.into();
+        And(A::match_only(target.clone()), B::match_only(target))
+    }
+
+    fn match_any() -> Self {
+        And(A::match_any(), B::match_any())
+    }
+
+    fn match_none() -> Self {
+        And(A::match_none(), B::match_none())
+    }
+}
+
+/// Matches targets matched by either constituent.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Or<A, B>(pub A, pub B);
+
+impl<T, A, B> Matcher for Or<A, B>
+where
+    A: Matcher<Target = T>,
+    B: Matcher<Target = T>,
+{
+    type Target = T;
+
+    fn test(&self, target: &Self::Target) -> bool {
+        self.0.test(target) || self.1.test(target)
+    }
+}
+
+impl<T, A, B> ExtendedMatcher for Or<A, B>
+where
+    T: Clone,
+    A: ExtendedMatcher<Target = T>,
+    B: ExtendedMatcher<Target = T>,
+{
+    type Target = T;
+
+    fn match_only<X: Into<Self::Target>>(target: X) -> Self {
+        let target = target.into();
+        Or(A::match_only(target.clone()), B::match_only(target))
+    }
+
+    fn match_any() -> Self {
+        Or(A::match_any(), B::match_any())
+    }
+
+    fn match_none() -> Self {
+        Or(A::match_none(), B::match_none())
+    }
+}
+
+/// Matches targets not matched by the constituent.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Not<M>(pub M);
+
+impl<M> Matcher for Not<M>
+where
+    M: Matcher,
+{
+    type Target = M::Target;
+
+    fn test(&self, target: &Self::Target) -> bool {
+        !self.0.test(target)
+    }
+}
+
+/// Matches targets matched by at least one constituent. Matches nothing if
+/// there are no constituents.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AnyOf<M>(pub Vec<M>);
+
+impl<M> Matcher for AnyOf<M>
+where
+    M: Matcher,
+{
+    type Target = M::Target;
+
+    fn test(&self, target: &Self::Target) -> bool {
+        self.0.iter().any(|m| m.test(target))
+    }
+}
+
+impl<M> ExtendedMatcher for AnyOf<M>
+where
+    M: ExtendedMatcher,
+{
+    type Target = M::Target;
+
+    fn match_only<X: Into<Self::Target>>(target: X) -> Self {
+        AnyOf(vec![M::match_only(target)])
+    }
+
+    fn match_any() -> Self {
+        AnyOf(vec![M::match_any()])
+    }
+
+    fn match_none() -> Self {
+        AnyOf(vec![])
+    }
+}
+
+impl<M> FromIterator<M> for AnyOf<M> {
+    fn from_iter<I: IntoIterator<Item = M>>(iter: I) -> Self {
+        AnyOf(iter.into_iter().collect())
+    }
+}
+
+/// Matches targets matched by every constituent. Matches everything if there
+/// are no constituents.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AllOf<M>(pub Vec<M>);
+
+impl<M> Matcher for AllOf<M>
+where
+    M: Matcher,
+{
+    type Target = M::Target;
+
+    fn test(&self, target: &Self::Target) -> bool {
+        self.0.iter().all(|m| m.test(target))
+    }
+}
+
+impl<M> ExtendedMatcher for AllOf<M>
+where
+    M: ExtendedMatcher,
+{
+    type Target = M::Target;
+
+    fn match_only<X: Into<Self::Target>>(target: X) -> Self {
+        AllOf(vec![M::match_only(target)])
+    }
+
+    fn match_any() -> Self {
+        AllOf(vec![])
+    }
+
+    fn match_none() -> Self {
+        AllOf(vec![M::match_none()])
+    }
+}
+
+impl<M> FromIterator<M> for AllOf<M> {
+    fn from_iter<I: IntoIterator<Item = M>>(iter: I) -> Self {
+        AllOf(iter.into_iter().collect())
+    }
+}
+
+/// Fluent construction of combined matchers.
+///
+/// # Examples
+///
+/// ```
+/// use authorization_core::matcher::*;
+/// use authorization_core::resource::*;
+///
+/// let matcher = StrResource("a").or(StrResource("b")).and(StrResource("b").not());
+///
+/// assert!(matcher.test(&StrResource("a")));
+/// assert!(!matcher.test(&StrResource("b")));
+/// assert!(!matcher.test(&StrResource("c")));
+/// ```
+pub trait MatcherExt: Matcher + Sized {
+    /// Combine with another matcher using `And`.
+    fn and<B: Matcher<Target = Self::Target>>(self, other: B) -> And<Self, B> {
+        And(self, other)
+    }
+
+    /// Combine with another matcher using `Or`.
+    fn or<B: Matcher<Target = Self::Target>>(self, other: B) -> Or<Self, B> {
+        Or(self, other)
+    }
+
+    /// Negate using `Not`.
+    fn not(self) -> Not<Self> {
+        Not(self)
+    }
+}
+
+impl<M> MatcherExt for M where M: Matcher {}
+
 // impl <T, M> From<T> for M: ExtendedMatcher<Target = T>
 // {
 //     fn from(v: T) -> Self {
 //         M::match_only(v)
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Matches a number divisible by the wrapped value.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    struct Divisor(u32);
+
+    impl Matcher for Divisor {
+        type Target = u32;
+
+        fn test(&self, target: &Self::Target) -> bool {
+            target % self.0 == 0
+        }
+    }
+
+    /// Exact matcher with the extended constructors.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    enum Exact {
+        Any,
+        Nothing,
+        Only(u32),
+    }
+
+    impl Matcher for Exact {
+        type Target = u32;
+
+        fn test(&self, target: &Self::Target) -> bool {
+            match self {
+                Exact::Any => true,
+                Exact::Nothing => false,
+                Exact::Only(v) => v == target,
+            }
+        }
+    }
+
+    impl ExtendedMatcher for Exact {
+        type Target = u32;
+
+        fn match_only<T: Into<Self::Target>>(target: T) -> Self {
+            Exact::Only(target.into())
+        }
+
+        fn match_any() -> Self {
+            Exact::Any
+        }
+
+        fn match_none() -> Self {
+            Exact::Nothing
+        }
+    }
+
+    #[test]
+    fn test_and() {
+        let matcher = And(Divisor(2), Divisor(3));
+
+        assert!(matcher.test(&6));
+        assert!(!matcher.test(&4));
+        assert!(!matcher.test(&9));
+    }
+
+    #[test]
+    fn test_or() {
+        let matcher = Or(Divisor(2), Divisor(3));
+
+        assert!(matcher.test(&4));
+        assert!(matcher.test(&9));
+        assert!(!matcher.test(&5));
+    }
+
+    #[test]
+    fn test_not() {
+        let matcher = Not(Divisor(2));
+
+        assert!(matcher.test(&3));
+        assert!(!matcher.test(&4));
+    }
+
+    #[test]
+    fn test_any_of() {
+        let matcher: AnyOf<_> = vec![Divisor(2), Divisor(5)].into_iter().collect();
+
+        assert!(matcher.test(&4));
+        assert!(matcher.test(&5));
+        assert!(!matcher.test(&7));
+        assert!(!AnyOf::<Divisor>(vec![]).test(&7));
+    }
+
+    #[test]
+    fn test_all_of() {
+        let matcher: AllOf<_> = vec![Divisor(2), Divisor(5)].into_iter().collect();
+
+        assert!(matcher.test(&10));
+        assert!(!matcher.test(&5));
+        assert!(!matcher.test(&4));
+        assert!(AllOf::<Divisor>(vec![]).test(&7));
+    }
+
+    #[test]
+    fn test_fluent() {
+        let matcher = Divisor(2).or(Divisor(3)).and(Divisor(4).not());
+
+        assert!(matcher.test(&6));
+        assert!(matcher.test(&9));
+        assert!(!matcher.test(&8));
+        assert!(!matcher.test(&7));
+    }
+
+    #[test]
+    fn test_ref_box_rc_arc() {
+        fn check<M: Matcher<Target = u32>>(m: M) {
+            assert!(m.test(&4));
+            assert!(!m.test(&3));
+        }
+
+        let divisor = Divisor(2);
+        check::<&Divisor>(&divisor);
+        check::<&&Divisor>(&&divisor);
+        check::<&dyn Matcher<Target = u32>>(&divisor);
+        check(Box::new(divisor));
+        check(Rc::new(divisor));
+        check(Arc::new(divisor));
+    }
+
+    #[test]
+    fn test_dyn() {
+        let matchers: Vec<DynMatcher<u32>> = vec![
+            Box::new(Divisor(2)),
+            Box::new(Exact::Only(3)),
+            Box::new(FnMatcher::new(|n: &u32| *n > 10)),
+        ];
+
+        assert!(matchers[0].test(&4));
+        assert!(matchers[1].test(&3));
+        assert!(matchers[2].test(&11));
+        assert!(!AnyOf(matchers).test(&7));
+    }
+
+    #[test]
+    fn test_fn_matcher() {
+        let limit = 5;
+        let matcher = FnMatcher::new(move |n: &u32| *n < limit);
+
+        assert!(matcher.clone().test(&4));
+        assert!(!matcher.test(&5));
+    }
+
+    #[test]
+    fn test_extended() {
+        fn check<M: Matcher<Target = u32> + ExtendedMatcher<Target = u32>>() {
+            assert!(M::match_only(3u32).test(&3));
+            assert!(!M::match_only(3u32).test(&4));
+            assert!(M::match_any().test(&4));
+            assert!(!M::match_none().test(&4));
+        }
+
+        check::<Exact>();
+        check::<And<Exact, Exact>>();
+        check::<Or<Exact, Exact>>();
+        check::<AnyOf<Exact>>();
+        check::<AllOf<Exact>>();
+    }
+}
@@ -0,0 +1,214 @@
+//! Policy statement identifiers and metadata.
+//!
+//! `Policy::Annotated(_, _)` attaches `Metadata` to a statement so tooling can
+//! reference it. Applying an annotated policy with an id wraps the resulting
+//! dependent effect in `DependentEffect::Identified(_, _)`, and
+//! `DependentEffect::resolve_attributed` reports the ids of the statements that
+//! decided the effect, as recorded by `DependentEffect::resolve_explained`.
+
+use super::dependent_effect::*;
+use super::effect::*;
+use super::environment::*;
+use super::explain::*;
+
+/// Descriptive information about a policy statement. All fields are optional.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+pub struct Metadata {
+    /// Stable identifier of the statement, reported by resolution.
+    pub id: Option<String>,
+    /// Human readable description of the statement.
+    pub description: Option<String>,
+    /// Free-form labels for the statement.
+    pub tags: Vec<String>,
+    /// Who is responsible for the statement.
+    pub owner: Option<String>,
+}
+
+impl Metadata {
+    /// Create metadata with no fields set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the id and return the metadata.
+    pub fn with_id<I: Into<String>>(mut self, id: I) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set the description and return the metadata.
+    pub fn with_description<D: Into<String>>(mut self, description: D) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Add a tag, unless present, and return the metadata.
+    pub fn with_tag<T: Into<String>>(mut self, tag: T) -> Self {
+        let tag = tag.into();
+        if !self.tags.contains(&tag) {
+            self.tags.push(tag);
+        }
+        self
+    }
+
+    /// Set the owner and return the metadata.
+    pub fn with_owner<O: Into<String>>(mut self, owner: O) -> Self {
+        self.owner = Some(owner.into());
+        self
+    }
+
+    /// Determine if the metadata has a tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+}
+
+/// A resolved effect with the ids of the statements that decided it.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Attribution {
+    /// The resolved effect.
+    pub effect: ComputedEffect,
+    /// Ids of the deciding statements, without duplicates and outermost first.
+    pub statements: Vec<String>,
+}
+
+impl From<ComputedEffect> for Attribution {
+    fn from(effect: ComputedEffect) -> Self {
+        Attribution {
+            effect,
+            statements: Vec::new(),
+        }
+    }
+}
+
+impl<CExp> DependentEffect<CExp> {
+    /// Evaluate dependent effect in an envionmental context, reporting the ids
+    /// of the `Identified` nodes that contributed to the effect, i.e. the
+    /// statements that allowed or denied the request. A silent decision is
+    /// attributed to no statement. Costs as much as `resolve_explained`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use authorization_core::dependent_effect::*;
+    /// use authorization_core::effect::*;
+    /// use authorization_core::environment::*;
+    /// use authorization_core::metadata::*;
+    ///
+    /// let effect = DependentEffect::Aggregate(vec![
+    ///     DependentEffect::Identified("read-own".into(), Box::new(DependentEffect::Fixed(Effect::ALLOW))),
+    ///     DependentEffect::Identified("suspended".into(), Box::new(DependentEffect::Atomic(Effect::DENY, ()))),
+    /// ]);
+    ///
+    /// let actual = effect.resolve_attributed(&PositiveEnvironment);
+    ///
+    /// assert_eq!(
+    ///     actual,
+    ///     Ok(Attribution { effect: DENY, statements: vec!["suspended".to_string()] })
+    /// );
+    /// ```
+    pub fn resolve_attributed<Env>(&self, environment: &Env) -> Result<Attribution, Env::Err>
+    where
+        Env: Environment<CExp = CExp>,
+    {
+        let (effect, trace) = self.resolve_explained(environment)?;
+        Ok(Attribution {
+            effect,
+            statements: trace.statements(),
+        })
+    }
+
+    /// Determine if any node identifies a statement.
+    pub fn is_identified(&self) -> bool {
+        self.any_node(|e| matches!(e, DependentEffect::Identified(_, _)))
+    }
+}
+
+impl<CExp> Trace<'_, CExp> {
+    /// The ids of the `Identified` nodes that contributed to the effect.
+    pub fn statements(&self) -> Vec<String> {
+        self.collect_contributing(Vec::new(), |ids: &mut Vec<String>, node| {
+            if let TraceNode::Identified { id, .. } = node {
+                if !ids.iter().any(|known| known == id) {
+                    ids.push(id.to_string());
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::explain::tests::Equals;
+
+    fn identified(id: &str, eff: DependentEffect<u32>) -> DependentEffect<u32> {
+        DependentEffect::Identified(id.to_string(), Box::new(eff))
+    }
+
+    #[test]
+    fn test_metadata() {
+        let metadata = Metadata::new()
+            .with_id("s1")
+            .with_description("readers can read")
+            .with_tag("read")
+            .with_tag("baseline")
+            .with_tag("read")
+            .with_owner("security");
+
+        assert_eq!(metadata.id.as_deref(), Some("s1"));
+        assert_eq!(metadata.description.as_deref(), Some("readers can read"));
+        assert_eq!(metadata.tags, vec!["read", "baseline"]);
+        assert_eq!(metadata.owner.as_deref(), Some("security"));
+        assert!(metadata.has_tag("baseline"));
+        assert!(!metadata.has_tag("write"));
+    }
+
+    #[test]
+    fn test_attribution() {
+        use DependentEffect::*;
+        let effect = Disjoint(vec![
+            identified(
+                "app",
+                Aggregate(vec![
+                    identified("app-read", Atomic(Effect::ALLOW, 1)),
+                    identified("app-deny", Atomic(Effect::DENY, 2)),
+                    identified("app-all", Fixed(Effect::ALLOW)),
+                ]),
+            ),
+            identified("user", Atomic(Effect::ALLOW, 1)),
+        ]);
+
+        let actual = effect.resolve_attributed(&Equals(1)).unwrap();
+        assert_eq!(actual.effect, ALLOW);
+        assert_eq!(
+            actual.statements,
+            vec!["app", "app-read", "app-all", "user"]
+        );
+
+        let actual = effect.resolve_attributed(&Equals(2)).unwrap();
+        assert_eq!(
+            actual,
+            Attribution {
+                effect: SILENT,
+                statements: vec![]
+            }
+        );
+
+        let effect = Aggregate(vec![
+            identified("a", Atomic(Effect::DENY, 2)),
+            identified("b", Atomic(Effect::ALLOW, 1)),
+            identified("a", Atomic(Effect::DENY, 2)),
+        ]);
+        let actual = effect.resolve_attributed(&Equals(2)).unwrap();
+        assert_eq!(actual.statements, vec!["a"]);
+    }
+
+    #[test]
+    fn test_is_identified() {
+        use DependentEffect::*;
+        assert!(!Aggregate(vec![Atomic(Effect::ALLOW, 1)]).is_identified());
+        assert!(Prioritized(vec![(1, identified("s", Silent))]).is_identified());
+    }
+}
@@ -0,0 +1,435 @@
+//! Network address conditions.
+//!
+//! `NetworkCondition`s restrict policies by the address a request came from.
+//! They are evaluated by a `NetworkEnvironment` that reads the caller address
+//! from an attribute of the request context, `request.ip` by default.
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use super::condition::*;
+use super::environment::*;
+
+/// Block of IPv4 or IPv6 addresses in CIDR notation e.g. `10.0.0.0/8` or
+/// `2001:db8::/32`. Host bits of the network address are cleared.
+///
+/// IPv4-mapped IPv6 addresses such as `::ffff:10.1.2.3` are treated as the
+/// IPv4 address they map. A block written with a mapped address must have a
+/// prefix of at least 96 bits so that it lies within the mapped IPv4 space.
+///
+/// # Examples
+///
+/// ```
+/// use authorization_core::network::*;
+///
+/// let block: Cidr = "10.1.2.3/8".parse().unwrap();
+///
+/// assert_eq!(block.to_string(), "10.0.0.0/8");
+/// assert!(block.contains(&"10.200.0.1".parse().unwrap()));
+/// assert!(!block.contains(&"11.0.0.1".parse().unwrap()));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    /// Construct a block from an address and prefix length. Fails if the
+    /// prefix is longer than the address, or shorter than 96 bits for an
+    /// IPv4-mapped address.
+    pub fn new(addr: IpAddr, prefix: u8) -> Result<Self, ParseCidrError> {
+        let invalid = || ParseCidrError::InvalidPrefix(prefix.to_string());
+        let (addr, prefix) = match addr.to_canonical() {
+            // the mapped range ::ffff:0:0/96 is the IPv4 space
+            a @ IpAddr::V4(_) if addr.is_ipv6() => (a, prefix.checked_sub(96).ok_or_else(invalid)?),
+            _ => (addr, prefix),
+        };
+        let network = match addr {
+            IpAddr::V4(a) if prefix <= 32 => IpAddr::V4((u32::from(a) & mask32(prefix)).into()),
+            IpAddr::V6(a) if prefix <= 128 => IpAddr::V6((u128::from(a) & mask128(prefix)).into()),
+            _ => return Err(invalid()),
+        };
+        Ok(Cidr { network, prefix })
+    }
+
+    /// Block containing exactly one address.
+    pub fn host(addr: IpAddr) -> Self {
+        let addr = addr.to_canonical();
+        let prefix = if addr.is_ipv4() { 32 } else { 128 };
+        Cidr {
+            network: addr,
+            prefix,
+        }
+    }
+
+    /// The first address of the block.
+    pub fn network(&self) -> IpAddr {
+        self.network
+    }
+
+    /// Number of leading bits fixed by the block.
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    /// Determine if an address is in the block. IPv4 blocks never contain
+    /// IPv6 addresses and vice versa.
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        match (self.network, addr.to_canonical()) {
+            (IpAddr::V4(n), IpAddr::V4(a)) => u32::from(a) & mask32(self.prefix) == u32::from(n),
+            (IpAddr::V6(n), IpAddr::V6(a)) => u128::from(a) & mask128(self.prefix) == u128::from(n),
+            _ => false,
+        }
+    }
+}
+
+/// Private helper. IPv4 netmask of a prefix length.
+fn mask32(prefix: u8) -> u32 {
+    u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0)
+}
+
+/// Private helper. IPv6 netmask of a prefix length.
+fn mask128(prefix: u8) -> u128 {
+    u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0)
+}
+
+impl From<IpAddr> for Cidr {
+    fn from(addr: IpAddr) -> Self {
+        Cidr::host(addr)
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix)
+    }
+}
+
+/// Error produced when parsing an address block.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ParseCidrError {
+    /// The address part is not an IPv4 or IPv6 address. Holds the address text.
+    InvalidAddress(String),
+    /// The prefix length is not a number, is too long for the address, or is
+    /// too short for an IPv4-mapped address. Holds the prefix text.
+    InvalidPrefix(String),
+}
+
+impl fmt::Display for ParseCidrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ParseCidrError::*;
+        match self {
+            InvalidAddress(s) => write!(f, "invalid address: {}", s),
+            InvalidPrefix(s) => write!(f, "invalid prefix length: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for ParseCidrError {}
+
+impl FromStr for Cidr {
+    type Err = ParseCidrError;
+
+    /// Parse `address/prefix`, or a bare address as a single host block.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|_| ParseCidrError::InvalidAddress(addr.to_string()))?;
+        match prefix {
+            None => Ok(Cidr::host(addr)),
+            Some(p) => {
+                // reject signs and whitespace that u8 parsing would accept
+                let prefix = p
+                    .bytes()
+                    .all(|b| b.is_ascii_digit())
+                    .then(|| p.parse::<u8>().ok())
+                    .flatten()
+                    .ok_or_else(|| ParseCidrError::InvalidPrefix(p.to_string()))?;
+                Cidr::new(addr, prefix)
+            }
+        }
+    }
+}
+
+/// Condition on the address of the caller.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum NetworkCondition {
+    /// Holds if the caller address is exactly the address.
+    Address(IpAddr),
+    /// Holds if the caller address is in the block.
+    Within(Cidr),
+    /// Holds if the caller address is in any of the blocks. An address set is
+    /// expressed with single host blocks.
+    WithinAny(Vec<Cidr>),
+    /// Holds if the constituent does not hold.
+    Not(Box<NetworkCondition>),
+}
+
+impl NetworkCondition {
+    /// Determine if the condition holds for a caller address.
+    pub fn holds_for(&self, caller: &IpAddr) -> bool {
+        use NetworkCondition::*;
+        match self {
+            Address(a) => a.to_canonical() == caller.to_canonical(),
+            Within(block) => block.contains(caller),
+            WithinAny(blocks) => blocks.iter().any(|b| b.contains(caller)),
+            Not(c) => !c.holds_for(caller),
+        }
+    }
+}
+
+/// Error produced when a network condition cannot be evaluated.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum NetworkError {
+    /// The request context has no caller address. Holds the attribute name.
+    MissingAddress(String),
+    /// The caller address in the request context is not a valid address.
+    /// Holds the offending text or a description of the value.
+    InvalidAddress(String),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use NetworkError::*;
+        match self {
+            MissingAddress(name) => write!(f, "no caller address in attribute: {}", name),
+            InvalidAddress(s) => write!(f, "invalid caller address: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+/// Environment evaluating `NetworkCondition`s against the caller address held
+/// as a string attribute of a request context. The context can be held by
+/// value or shared e.g. as `&AttributeEnvironment`.
+///
+/// # Examples
+///
+/// ```
+/// use authorization_core::condition::*;
+/// use authorization_core::environment::*;
+/// use authorization_core::network::*;
+///
+/// let internal = NetworkCondition::Within("10.0.0.0/8".parse().unwrap());
+///
+/// let context = AttributeEnvironment::new().with("request.ip", "10.1.2.3");
+/// assert_eq!(NetworkEnvironment::new(&context).test_condition(&internal), Ok(true));
+///
+/// let context = AttributeEnvironment::new().with("request.ip", "10.1.2");
+/// assert_eq!(
+///     NetworkEnvironment::new(&context).test_condition(&internal),
+///     Err(NetworkError::InvalidAddress("10.1.2".into()))
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct NetworkEnvironment<C> {
+    context: C,
+    attribute: String,
+}
+
+impl<C> NetworkEnvironment<C>
+where
+    C: Borrow<AttributeEnvironment>,
+{
+    /// Attribute holding the caller address unless otherwise specified.
+    pub const DEFAULT_ATTRIBUTE: &'static str = "request.ip";
+
+    /// Create an environment reading the caller address from `request.ip`.
+    pub fn new(context: C) -> Self {
+        Self::with_attribute(context, Self::DEFAULT_ATTRIBUTE)
+    }
+
+    /// Create an environment reading the caller address from the named attribute.
+    pub fn with_attribute<N: Into<String>>(context: C, attribute: N) -> Self {
+        NetworkEnvironment {
+            context,
+            attribute: attribute.into(),
+        }
+    }
+
+    /// Read and parse the caller address from the request context.
+    pub fn caller(&self) -> Result<IpAddr, NetworkError> {
+        match self.context.borrow().get(&self.attribute) {
+            None => Err(NetworkError::MissingAddress(self.attribute.clone())),
+            Some(Value::Str(s)) => s
+                .parse()
+                .map_err(|_| NetworkError::InvalidAddress(s.clone())),
+            Some(other) => Err(NetworkError::InvalidAddress(format!(
+                "expected string, found {}",
+                other.type_name()
+            ))),
+        }
+    }
+}
+
+impl<C> Environment for NetworkEnvironment<C>
+where
+    C: Borrow<AttributeEnvironment>,
+{
+    type Err = NetworkError;
+    type CExp = NetworkCondition;
+
+    fn test_condition(&self, exp: &Self::CExp) -> Result<bool, Self::Err> {
+        self.caller().map(|caller| exp.holds_for(&caller))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dependent_effect::*;
+    use crate::effect::*;
+
+    fn addr(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn cidr(s: &str) -> Cidr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_cidr() {
+        assert_eq!(cidr("192.168.1.77/24").to_string(), "192.168.1.0/24");
+        assert_eq!(cidr("10.0.0.1").to_string(), "10.0.0.1/32");
+        assert_eq!(cidr("0.0.0.0/0").to_string(), "0.0.0.0/0");
+        assert_eq!(cidr("2001:db8::1/32").to_string(), "2001:db8::/32");
+        assert_eq!(cidr("::1").to_string(), "::1/128");
+        assert_eq!(cidr("::ffff:10.1.2.3/104").to_string(), "10.0.0.0/8");
+    }
+
+    #[test]
+    fn test_parse_cidr_errors() {
+        use ParseCidrError::*;
+
+        assert_eq!(
+            "10.0.0/8".parse::<Cidr>(),
+            Err(InvalidAddress("10.0.0".into()))
+        );
+        assert_eq!(
+            "10.0.0.0/33".parse::<Cidr>(),
+            Err(InvalidPrefix("33".into()))
+        );
+        assert_eq!("10.0.0.0/".parse::<Cidr>(), Err(InvalidPrefix("".into())));
+        assert_eq!(
+            "10.0.0.0/+8".parse::<Cidr>(),
+            Err(InvalidPrefix("+8".into()))
+        );
+        assert_eq!("::/129".parse::<Cidr>(), Err(InvalidPrefix("129".into())));
+        assert_eq!("::/300".parse::<Cidr>(), Err(InvalidPrefix("300".into())));
+        assert_eq!(
+            "::ffff:10.0.0.0/80".parse::<Cidr>(),
+            Err(InvalidPrefix("80".into()))
+        );
+        assert_eq!(
+            "::ffff:10.0.0.0/129".parse::<Cidr>(),
+            Err(InvalidPrefix("129".into()))
+        );
+    }
+
+    #[test]
+    fn test_contains() {
+        let v4 = cidr("172.16.0.0/12");
+        assert!(v4.contains(&addr("172.16.0.0")));
+        assert!(v4.contains(&addr("172.31.255.255")));
+        assert!(!v4.contains(&addr("172.32.0.0")));
+        assert!(v4.contains(&addr("::ffff:172.20.1.1")));
+        assert!(!v4.contains(&addr("2001:db8::1")));
+
+        let v6 = cidr("2001:db8::/32");
+        assert!(v6.contains(&addr("2001:db8:ffff::1")));
+        assert!(!v6.contains(&addr("2001:db9::1")));
+        assert!(!v6.contains(&addr("10.0.0.1")));
+
+        assert!(cidr("0.0.0.0/0").contains(&addr("8.8.8.8")));
+        assert!(cidr("::/0").contains(&addr("::1")));
+    }
+
+    #[test]
+    fn test_conditions() {
+        use NetworkCondition::*;
+
+        assert!(Address(addr("10.0.0.1")).holds_for(&addr("10.0.0.1")));
+        assert!(Address(addr("10.0.0.1")).holds_for(&addr("::ffff:10.0.0.1")));
+        assert!(!Address(addr("10.0.0.1")).holds_for(&addr("10.0.0.2")));
+        assert!(Address(addr("2001:db8::1")).holds_for(&addr("2001:db8:0::1")));
+
+        let allowed = WithinAny(vec![
+            cidr("10.0.0.0/8"),
+            cidr("192.168.1.5"),
+            cidr("fd00::/8"),
+        ]);
+        assert!(allowed.holds_for(&addr("10.9.9.9")));
+        assert!(allowed.holds_for(&addr("192.168.1.5")));
+        assert!(!allowed.holds_for(&addr("192.168.1.6")));
+        assert!(allowed.holds_for(&addr("fd12::1")));
+        assert!(!WithinAny(vec![]).holds_for(&addr("10.0.0.1")));
+
+        assert!(Not(Box::new(Within(cidr("10.0.0.0/8")))).holds_for(&addr("11.0.0.1")));
+    }
+
+    #[test]
+    fn test_environment_errors() {
+        let cond = NetworkCondition::Within(cidr("10.0.0.0/8"));
+
+        let context = AttributeEnvironment::new();
+        assert_eq!(
+            NetworkEnvironment::new(&context).test_condition(&cond),
+            Err(NetworkError::MissingAddress("request.ip".into()))
+        );
+
+        let context = AttributeEnvironment::new().with("request.ip", 10);
+        assert_eq!(
+            NetworkEnvironment::new(&context).test_condition(&cond),
+            Err(NetworkError::InvalidAddress(
+                "expected string, found number".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_environment_attribute() {
+        let context = AttributeEnvironment::new()
+            .with("request.ip", "10.0.0.1")
+            .with("source", "192.168.0.1");
+        let cond = NetworkCondition::Within(cidr("192.168.0.0/16"));
+
+        assert_eq!(
+            NetworkEnvironment::new(&context).test_condition(&cond),
+            Ok(false)
+        );
+        assert_eq!(
+            NetworkEnvironment::with_attribute(context, "source").test_condition(&cond),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn test_network_environment_resolves_effects() {
+        let effect = DependentEffect::Aggregate(vec![
+            DependentEffect::Atomic(Effect::ALLOW, NetworkCondition::Within(cidr("10.0.0.0/8"))),
+            DependentEffect::Atomic(Effect::DENY, NetworkCondition::Address(addr("10.0.0.66"))),
+        ]);
+        let resolve = |ip: &str| {
+            let context = AttributeEnvironment::new().with("request.ip", ip);
+            effect.resolve(&NetworkEnvironment::new(context))
+        };
+
+        assert_eq!(resolve("10.1.1.1"), Ok(ALLOW));
+        assert_eq!(resolve("10.0.0.66"), Ok(DENY));
+        assert_eq!(resolve("8.8.8.8"), Ok(SILENT));
+        assert_eq!(
+            resolve("localhost"),
+            Err(NetworkError::InvalidAddress("localhost".into()))
+        );
+    }
+}
@@ -0,0 +1,409 @@
+//! Obligations and advice attached to effects.
+//!
+//! A decision often carries follow-up duties for the enforcement point, e.g.
+//! "allow, but log to the audit stream" or "deny, and show message X". Policies
+//! attach `Duties` to statements with `Policy::Obligated(_, _)`. Resolving with
+//! `DependentEffect::resolve_with_duties` returns a `Decision` holding the
+//! duties of only those statements whose effects decided the result, as
+//! recorded by `DependentEffect::resolve_explained`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::dependent_effect::*;
+use super::effect::*;
+use super::environment::*;
+use super::explain::*;
+
+/// A duty attached to a decision, either an obligation the enforcement point
+/// must fulfil or advice it may ignore.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Duty {
+    /// Identifies the duty.
+    pub id: String,
+    /// Arguments of the duty by name, at most one value per name.
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl Duty {
+    /// Create a duty without arguments.
+    pub fn new<I: Into<String>>(id: I) -> Self {
+        Duty {
+            id: id.into(),
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    /// Set an argument, replacing any value of the same name, and return the
+    /// duty.
+    pub fn with<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        self.attributes.insert(name.into(), value.into());
+        self
+    }
+
+    /// The value of an argument.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+}
+
+/// Obligations and advice, each without duplicates and in order of first
+/// occurrence.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+pub struct Duties {
+    /// Duties that must be fulfilled to enforce the decision.
+    pub obligations: Vec<Duty>,
+    /// Duties that should be fulfilled but may be ignored.
+    pub advice: Vec<Duty>,
+}
+
+impl Duties {
+    /// Create an empty set of duties.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an obligation, unless present, and return the duties.
+    pub fn with_obligation(mut self, obligation: Duty) -> Self {
+        if !self.obligations.contains(&obligation) {
+            self.obligations.push(obligation);
+        }
+        self
+    }
+
+    /// Add advice, unless present, and return the duties.
+    pub fn with_advice(mut self, advice: Duty) -> Self {
+        if !self.advice.contains(&advice) {
+            self.advice.push(advice);
+        }
+        self
+    }
+
+    /// Determine if there are no obligations and no advice.
+    pub fn is_empty(&self) -> bool {
+        self.obligations.is_empty() && self.advice.is_empty()
+    }
+
+    /// Add the obligations and advice not already present.
+    pub fn merge(&mut self, other: &Duties) {
+        for obligation in &other.obligations {
+            if !self.obligations.contains(obligation) {
+                self.obligations.push(obligation.clone());
+            }
+        }
+        for advice in &other.advice {
+            if !self.advice.contains(advice) {
+                self.advice.push(advice.clone());
+            }
+        }
+    }
+}
+
+impl fmt::Display for Duty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.id)?;
+        if !self.attributes.is_empty() {
+            let args: Vec<String> = self
+                .attributes
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect();
+            write!(f, "({})", args.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Duties {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let obligations: Vec<String> = self.obligations.iter().map(|o| o.to_string()).collect();
+        let advice: Vec<String> = self.advice.iter().map(|a| a.to_string()).collect();
+        match (obligations.is_empty(), advice.is_empty()) {
+            (true, true) => f.write_str("no duties"),
+            (false, true) => write!(f, "obligations {}", obligations.join(", ")),
+            (true, false) => write!(f, "advice {}", advice.join(", ")),
+            (false, false) => write!(
+                f,
+                "obligations {}; advice {}",
+                obligations.join(", "),
+                advice.join(", ")
+            ),
+        }
+    }
+}
+
+/// A resolved effect with the duties of the statements that decided it.
+///
+/// The duties are kept beside the effect rather than in `ComputedEffect`, which
+/// stays a `Copy` value combined by every resolution. Only resolutions that
+/// collect duties pay for them.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Decision {
+    /// The resolved effect.
+    pub effect: ComputedEffect,
+    /// The obligations and advice to be fulfilled with the effect.
+    pub duties: Duties,
+}
+
+impl Decision {
+    /// Determine if the decision authorizes access. The obligations must still
+    /// be fulfilled to enforce it.
+    pub fn authorized(&self) -> bool {
+        self.effect.authorized()
+    }
+}
+
+impl From<ComputedEffect> for Decision {
+    fn from(effect: ComputedEffect) -> Self {
+        Decision {
+            effect,
+            duties: Duties::new(),
+        }
+    }
+}
+
+impl<CExp> DependentEffect<CExp> {
+    /// Evaluate dependent effect in an envionmental context, collecting the
+    /// duties of the `Obligated` nodes that contributed to the effect. A silent
+    /// decision carries no duties. Costs as much as `resolve_explained`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use authorization_core::dependent_effect::*;
+    /// use authorization_core::effect::*;
+    /// use authorization_core::environment::*;
+    /// use authorization_core::obligation::*;
+    ///
+    /// struct Equals(u32);
+    ///
+    /// impl Environment for Equals {
+    ///     type Err = ();
+    ///     type CExp = u32;
+    ///
+    ///     fn test_condition(&self, exp: &u32) -> Result<bool, ()> {
+    ///         Ok(self.0 == *exp)
+    ///     }
+    /// }
+    ///
+    /// let audit = Duties::new().with_obligation(Duty::new("audit").with("stream", "security"));
+    /// let message = Duties::new().with_advice(Duty::new("show").with("text", "read only"));
+    /// let effect = DependentEffect::Aggregate(vec![
+    ///     DependentEffect::Obligated(audit.clone(), Box::new(DependentEffect::Atomic(Effect::ALLOW, 1))),
+    ///     DependentEffect::Obligated(message.clone(), Box::new(DependentEffect::Atomic(Effect::DENY, 2))),
+    /// ]);
+    ///
+    /// let decision = effect.resolve_with_duties(&Equals(1)).unwrap();
+    /// assert_eq!(decision, Decision { effect: ALLOW, duties: audit });
+    ///
+    /// let decision = effect.resolve_with_duties(&Equals(2)).unwrap();
+    /// assert_eq!(decision, Decision { effect: DENY, duties: message });
+    ///
+    /// let decision = effect.resolve_with_duties(&Equals(3)).unwrap();
+    /// assert_eq!(decision, SILENT.into());
+    /// ```
+    pub fn resolve_with_duties<Env>(&self, environment: &Env) -> Result<Decision, Env::Err>
+    where
+        Env: Environment<CExp = CExp>,
+    {
+        let (effect, trace) = self.resolve_explained(environment)?;
+        Ok(Decision {
+            effect,
+            duties: trace.duties(),
+        })
+    }
+
+    /// Determine if any node attaches duties.
+    pub fn is_obligated(&self) -> bool {
+        self.any_node(|e| matches!(e, DependentEffect::Obligated(duties, _) if !duties.is_empty()))
+    }
+}
+
+impl<CExp> Trace<'_, CExp> {
+    /// The duties of the `Obligated` nodes that contributed to the effect.
+    pub fn duties(&self) -> Duties {
+        self.collect_contributing(Duties::new(), |duties, node| {
+            if let TraceNode::Obligated {
+                duties: attached, ..
+            } = node
+            {
+                duties.merge(attached);
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::explain::tests::Equals;
+
+    fn obligation(id: &str) -> Duties {
+        Duties::new().with_obligation(Duty::new(id))
+    }
+
+    fn obligated(id: &str, eff: DependentEffect<u32>) -> DependentEffect<u32> {
+        DependentEffect::Obligated(obligation(id), Box::new(eff))
+    }
+
+    #[test]
+    fn test_only_contributing_statements() {
+        use DependentEffect::*;
+        let effect = Disjoint(vec![
+            Aggregate(vec![
+                obligated("allow-1", Atomic(Effect::ALLOW, 1)),
+                obligated("deny-2", Atomic(Effect::DENY, 2)),
+                obligated("allow", Fixed(Effect::ALLOW)),
+            ]),
+            obligated(
+                "second",
+                Aggregate(vec![Atomic(Effect::ALLOW, 1), Atomic(Effect::ALLOW, 2)]),
+            ),
+        ]);
+
+        let actual = effect.resolve_with_duties(&Equals(1)).unwrap();
+        assert_eq!(actual.effect, ALLOW);
+        let ids: Vec<&str> = actual
+            .duties
+            .obligations
+            .iter()
+            .map(|o| o.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["allow-1", "allow", "second"]);
+
+        // the allowing statements did not contribute to the denial
+        let actual = effect.resolve_with_duties(&Equals(2)).unwrap();
+        assert_eq!(
+            actual,
+            Decision {
+                effect: DENY,
+                duties: obligation("deny-2")
+            }
+        );
+
+        // nothing contributes to silence
+        let actual = effect.resolve_with_duties(&Equals(3)).unwrap();
+        assert_eq!(actual, SILENT.into());
+    }
+
+    #[test]
+    fn test_first_applicable_and_priority() {
+        use DependentEffect::*;
+        let effect = Combined(
+            Algorithm::FirstApplicable,
+            vec![
+                obligated("first", Atomic(Effect::DENY, 1)),
+                obligated("second", Fixed(Effect::DENY)),
+            ],
+        );
+        assert_eq!(
+            effect.resolve_with_duties(&Equals(1)).unwrap().duties,
+            obligation("first")
+        );
+        assert_eq!(
+            effect.resolve_with_duties(&Equals(2)).unwrap().duties,
+            obligation("second")
+        );
+
+        let effect = Prioritized(vec![
+            (0, obligated("general", Fixed(Effect::DENY))),
+            (1, obligated("emergency", Atomic(Effect::ALLOW, 1))),
+        ]);
+        assert_eq!(
+            effect.resolve_with_duties(&Equals(1)).unwrap(),
+            Decision {
+                effect: ALLOW,
+                duties: obligation("emergency")
+            }
+        );
+    }
+
+    #[test]
+    fn test_only_one_applicable_conflict() {
+        use DependentEffect::*;
+        let effect = Combined(
+            Algorithm::OnlyOneApplicable,
+            vec![
+                obligated("log-read", Atomic(Effect::ALLOW, 1)),
+                obligated("log-all", Fixed(Effect::ALLOW)),
+            ],
+        );
+
+        // the conflict denies, and neither allowing statement contributed
+        assert_eq!(effect.resolve_with_duties(&Equals(1)).unwrap(), DENY.into());
+        assert_eq!(
+            effect.resolve_with_duties(&Equals(2)).unwrap(),
+            Decision {
+                effect: ALLOW,
+                duties: obligation("log-all")
+            }
+        );
+    }
+
+    #[test]
+    fn test_duplicates_are_collected_once() {
+        use DependentEffect::*;
+        let duties = Duties::new()
+            .with_obligation(Duty::new("audit"))
+            .with_advice(Duty::new("notify").with("to", "owner"));
+        let effect = Aggregate(vec![
+            Obligated(duties.clone(), Box::new(Fixed(Effect::ALLOW))),
+            Obligated(
+                duties.clone().with_obligation(Duty::new("audit")),
+                Box::new(obligated("log", Fixed(Effect::ALLOW))),
+            ),
+        ]);
+
+        let actual = effect.resolve_with_duties(&Equals(0)).unwrap();
+
+        assert_eq!(actual.duties, duties.with_obligation(Duty::new("log")));
+        assert!(actual.authorized());
+    }
+
+    #[test]
+    fn test_is_obligated() {
+        use DependentEffect::*;
+        assert!(!Aggregate(vec![Atomic(Effect::ALLOW, 1)]).is_obligated());
+        assert!(!Obligated::<u32>(Duties::new(), Box::new(Fixed(Effect::ALLOW))).is_obligated());
+        assert!(Prioritized(vec![(1, obligated("o", Silent))]).is_obligated());
+    }
+
+    #[test]
+    fn test_display() {
+        let duties = Duties::new()
+            .with_obligation(Duty::new("audit").with("stream", "security"))
+            .with_obligation(Duty::new("log"))
+            .with_advice(Duty::new("notify"));
+
+        assert_eq!(
+            duties.to_string(),
+            "obligations audit(stream=security), log; advice notify"
+        );
+        assert_eq!(
+            Duties::new().with_advice(Duty::new("notify")).to_string(),
+            "advice notify"
+        );
+        assert_eq!(Duties::new().to_string(), "no duties");
+        assert_eq!(
+            Duty::new("log")
+                .with("to", "b")
+                .with("level", "info")
+                .to_string(),
+            "log(level=info, to=b)"
+        );
+    }
+
+    #[test]
+    fn test_duty_attributes() {
+        let duty = Duty::new("log").with("to", "audit").with("to", "security");
+
+        assert_eq!(duty.get("to"), Some("security"));
+        assert_eq!(duty.get("level"), None);
+        assert_eq!(duty.attributes.len(), 1);
+    }
+}
@@ -0,0 +1,561 @@
+//! Partial evaluation of dependent effects.
+//!
+//! When only some conditions can be decided up front, e.g. from the claims of a
+//! token, `DependentEffect::partially_resolve` folds the decided branches and
+//! returns a residual dependent effect holding the conditions still to be
+//! tested. Resolving the residual in any environment consistent with the known
+//! values produces the same effect as resolving the original.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::dependent_effect::*;
+use super::effect::*;
+use super::environment::*;
+
+/// Context that can decide some conditions but not others.
+pub trait PartialEnvironment {
+    /// The type of conditional expression that can be evaluated in the environment.
+    type CExp;
+
+    /// Test that a condition holds with respect to the environment, or `None`
+    /// if it cannot be decided.
+    fn partially_test_condition(&self, exp: &Self::CExp) -> Option<bool>;
+}
+
+impl<T> PartialEnvironment for T
+where
+    T: ReliableEnvironment,
+{
+    type CExp = <Self as ReliableEnvironment>::CExp;
+
+    fn partially_test_condition(&self, exp: &Self::CExp) -> Option<bool> {
+        Some(self.reliably_test_condition(exp))
+    }
+}
+
+/// Conditions with known values. Other conditions are undecided.
+impl<CExp> PartialEnvironment for HashMap<CExp, bool>
+where
+    CExp: Eq + Hash,
+{
+    type CExp = CExp;
+
+    fn partially_test_condition(&self, exp: &Self::CExp) -> Option<bool> {
+        self.get(exp).copied()
+    }
+}
+
+impl<CExp> DependentEffect<CExp>
+where
+    CExp: Clone,
+{
+    /// Evaluate the conditions decidable in an environment and return the
+    /// residual dependent effect.
+    ///
+    /// Decided conditions become `Fixed` or `Silent`. An `Aggregate` containing a
+    /// `DENY` becomes `Fixed(DENY)` and a `Disjoint` containing silence becomes
+    /// `Silent`. Constituents that cannot affect a combination are dropped
+    /// (silence in an `Aggregate`, `ALLOW` in a non-empty `Disjoint`) and a
+    /// combination left with a single constituent is replaced by it. A
+    /// `Combined` keeps all its constituents, since their positions can matter
+    /// to its algorithm, and is replaced by its result once the decided
+    /// constituents leading it decide it. A `Prioritized` drops silence and the
+    /// constituents below the priority of a decided effect, and is replaced by
+    /// its result once the highest-priority constituents decide it.
+    ///
+    /// Constituents carrying duties or statement ids are kept while they can
+    /// contribute to the effect, so the residual reports the same duties and
+    /// statements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use authorization_core::dependent_effect::*;
+    /// use authorization_core::effect::*;
+    ///
+    /// let effect = DependentEffect::Aggregate(vec![
+    ///     DependentEffect::Atomic(Effect::ALLOW, "from token"),
+    ///     DependentEffect::Atomic(Effect::DENY, "from database"),
+    /// ]);
+    /// let known = HashMap::from([("from token", true)]);
+    ///
+    /// let actual = effect.partially_resolve(&known);
+    ///
+    /// assert_eq!(
+    ///     actual,
+    ///     DependentEffect::Aggregate(vec![
+    ///         DependentEffect::Fixed(Effect::ALLOW),
+    ///         DependentEffect::Atomic(Effect::DENY, "from database"),
+    ///     ])
+    /// );
+    /// ```
+    pub fn partially_resolve<Env>(&self, environment: &Env) -> DependentEffect<CExp>
+    where
+        Env: PartialEnvironment<CExp = CExp>,
+    {
+        use DependentEffect::*;
+        match self {
+            Silent => Silent,
+            Fixed(eff) => Fixed(*eff),
+            Atomic(eff, cexp) => match environment.partially_test_condition(cexp) {
+                Some(true) => Fixed(*eff),
+                Some(false) => Silent,
+                None => Atomic(*eff, cexp.clone()),
+            },
+            Aggregate(effs) => {
+                let mut residual = Vec::with_capacity(effs.len());
+                let mut denied = false;
+                for eff in effs {
+                    match eff.partially_resolve(environment) {
+                        Fixed(Effect::DENY) => denied = true,
+                        Silent => {}
+                        r => residual.push(r),
+                    }
+                }
+                if denied {
+                    denied_aggregate(residual)
+                } else {
+                    fold(residual, Aggregate, combine_non_strict)
+                }
+            }
+            Disjoint(effs) => {
+                let mut residual = Vec::with_capacity(effs.len());
+                let mut allowed = false;
+                for eff in effs {
+                    match eff.partially_resolve(environment) {
+                        Silent => return Silent,
+                        Fixed(Effect::ALLOW) => allowed = true,
+                        r => residual.push(r),
+                    }
+                }
+                if residual.is_empty() && allowed {
+                    Fixed(Effect::ALLOW)
+                } else {
+                    fold(residual, Disjoint, combine_strict)
+                }
+            }
+            Combined(algorithm, effs) => {
+                let residual: Vec<_> = effs
+                    .iter()
+                    .map(|e| e.partially_resolve(environment))
+                    .collect();
+                match decided_prefix(&residual, algorithm) {
+                    Some(decided) if !residual.iter().any(annotated) => fixed(decided),
+                    _ => Combined(algorithm.clone(), residual),
+                }
+            }
+            Prioritized(effs) => prune_prioritized(
+                effs.iter()
+                    .map(|(p, e)| (*p, e.partially_resolve(environment)))
+                    .collect(),
+            ),
+            Obligated(duties, eff) => match eff.partially_resolve(environment) {
+                Silent => Silent,
+                r => Obligated(duties.clone(), Box::new(r)),
+            },
+            Identified(id, eff) => match eff.partially_resolve(environment) {
+                Silent => Silent,
+                r => Identified(id.clone(), Box::new(r)),
+            },
+        }
+    }
+}
+
+/// Private helper. Replace a combination of fixed effects by its result and a
+/// combination of one constituent by the constituent.
+fn fold<CExp, C, F>(
+    mut effs: Vec<DependentEffect<CExp>>,
+    make: C,
+    combine: F,
+) -> DependentEffect<CExp>
+where
+    C: Fn(Vec<DependentEffect<CExp>>) -> DependentEffect<CExp>,
+    F: Fn(Vec<ComputedEffect>) -> ComputedEffect,
+{
+    use DependentEffect::*;
+    let decided: Option<Vec<ComputedEffect>> = effs
+        .iter()
+        .map(|e| match e {
+            Silent => Some(SILENT),
+            Fixed(eff) => Some((*eff).into()),
+            _ => None,
+        })
+        .collect();
+    if let Some(effects) = decided {
+        return fixed(combine(effects));
+    }
+    if effs.len() == 1 {
+        return effs.pop().unwrap();
+    }
+    make(effs)
+}
+
+/// Private helper. The result of a combination if its leading decided
+/// constituents decide it.
+pub(crate) fn decided_prefix<CExp>(
+    effs: &[DependentEffect<CExp>],
+    algorithm: &dyn CombiningAlgorithm,
+) -> Option<ComputedEffect> {
+    let mut prefix = Vec::new();
+    for eff in effs {
+        if let Some(decided) = algorithm.decided(&prefix) {
+            return Some(decided);
+        }
+        match eff {
+            DependentEffect::Silent => prefix.push(SILENT),
+            DependentEffect::Fixed(e) => prefix.push((*e).into()),
+            _ => return None,
+        }
+    }
+    Some(
+        algorithm
+            .decided(&prefix)
+            .unwrap_or_else(|| algorithm.combine(&prefix)),
+    )
+}
+
+/// Private helper. Drop the constituents of a prioritized combination that
+/// cannot affect its result, replacing it by its result if decided and by its
+/// constituent if only one remains.
+pub(crate) fn prune_prioritized<CExp>(
+    effs: Vec<(Priority, DependentEffect<CExp>)>,
+) -> DependentEffect<CExp> {
+    use DependentEffect::*;
+    let floor = effs
+        .iter()
+        .filter(|(_, e)| matches!(e, Fixed(_)))
+        .map(|(p, _)| *p)
+        .max();
+    let mut effs: Vec<_> = effs
+        .into_iter()
+        .filter(|(p, e)| !matches!(e, Silent) && !matches!(floor, Some(f) if *p < f))
+        .collect();
+    let top = effs.iter().map(|(p, _)| *p).max();
+    // fixed effects carry no duties or ids, so a combination of only fixed
+    // effects loses nothing by being replaced with its result, whereas a DENY
+    // at the top priority must not hide annotated constituents it ties with
+    let decided = effs.iter().all(|(_, e)| matches!(e, Fixed(_)))
+        || (effs
+            .iter()
+            .any(|(p, e)| Some(*p) == top && matches!(e, Fixed(Effect::DENY)))
+            && !effs.iter().any(|(_, e)| annotated(e)));
+    if decided {
+        return fixed(combine_prioritized(effs.iter().filter_map(
+            |(p, e)| match e {
+                Fixed(eff) => Some((*p, (*eff).into())),
+                _ => None,
+            },
+        )));
+    }
+    if effs.len() == 1 {
+        return effs.pop().unwrap().1;
+    }
+    Prioritized(effs)
+}
+
+/// Private helper. Determine if an effect carries duties or statement ids,
+/// which are reported if it contributes to a result.
+pub(crate) fn annotated<CExp>(eff: &DependentEffect<CExp>) -> bool {
+    eff.is_obligated() || eff.is_identified()
+}
+
+/// Private helper. A non-strict combination known to resolve to `DENY`, keeping
+/// the annotated constituents, which contribute if they also deny.
+pub(crate) fn denied_aggregate<CExp>(effs: Vec<DependentEffect<CExp>>) -> DependentEffect<CExp> {
+    let mut kept: Vec<_> = effs.into_iter().filter(annotated).collect();
+    if kept.is_empty() {
+        return DependentEffect::Fixed(Effect::DENY);
+    }
+    kept.insert(0, DependentEffect::Fixed(Effect::DENY));
+    DependentEffect::Aggregate(kept)
+}
+
+/// Private helper. The unconditional dependent effect resolving to an effect.
+pub(crate) fn fixed<CExp>(effect: ComputedEffect) -> DependentEffect<CExp> {
+    match effect {
+        ALLOW => DependentEffect::Fixed(Effect::ALLOW),
+        DENY => DependentEffect::Fixed(Effect::DENY),
+        _ => DependentEffect::Silent,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+
+    use super::*;
+
+    /// Environment deciding conditions from a bit set of the conditions that
+    /// hold. Shared with the tests of simplification.
+    pub(crate) struct Bits(pub(crate) u32);
+
+    impl ReliableEnvironment for Bits {
+        type CExp = u32;
+
+        fn reliably_test_condition(&self, exp: &Self::CExp) -> bool {
+            self.0 & (1 << exp) != 0
+        }
+    }
+
+    /// Private helper. Dependent effects over conditions 0, 1 and 2 up to a depth.
+    fn effects(depth: usize) -> Vec<DependentEffect<u32>> {
+        use DependentEffect::*;
+        let mut effs = vec![Silent, Fixed(Effect::ALLOW), Fixed(Effect::DENY)];
+        for c in 0..3 {
+            effs.push(Atomic(Effect::ALLOW, c));
+            effs.push(Atomic(Effect::DENY, c));
+        }
+        if depth > 0 {
+            let inner = effects(depth - 1);
+            let mut combined = Vec::new();
+            for (i, a) in inner.iter().enumerate().step_by(2) {
+                for b in inner.iter().skip(i % 5).step_by(3) {
+                    combined.push(Aggregate(vec![a.clone(), b.clone()]));
+                    combined.push(Disjoint(vec![b.clone(), a.clone()]));
+                    combined.push(Aggregate(vec![
+                        a.clone(),
+                        b.clone(),
+                        Atomic(Effect::ALLOW, 2),
+                    ]));
+                    combined.push(Disjoint(vec![a.clone(), Fixed(Effect::ALLOW), b.clone()]));
+                    combined.push(Combined(
+                        Algorithm::ALL[combined.len() % Algorithm::ALL.len()].clone(),
+                        vec![a.clone(), b.clone()],
+                    ));
+                    combined.push(Prioritized(vec![
+                        (1, a.clone()),
+                        (combined.len() as Priority % 3, b.clone()),
+                    ]));
+                }
+            }
+            effs.extend(combined);
+            effs.push(Aggregate(vec![]));
+            effs.push(Disjoint(vec![]));
+        }
+        effs
+    }
+
+    #[test]
+    fn test_atomic() {
+        use DependentEffect::*;
+        let known = HashMap::from([(1, true), (2, false)]);
+
+        assert_eq!(
+            Atomic(Effect::DENY, 1).partially_resolve(&known),
+            Fixed(Effect::DENY)
+        );
+        assert_eq!(Atomic(Effect::DENY, 2).partially_resolve(&known), Silent);
+        assert_eq!(
+            Atomic(Effect::DENY, 3).partially_resolve(&known),
+            Atomic(Effect::DENY, 3)
+        );
+    }
+
+    #[test]
+    fn test_aggregate() {
+        use DependentEffect::*;
+        let known = HashMap::from([(1, true), (2, false)]);
+
+        let effect = Aggregate(vec![Atomic(Effect::ALLOW, 3), Atomic(Effect::DENY, 1)]);
+        assert_eq!(effect.partially_resolve(&known), Fixed(Effect::DENY));
+
+        let effect = Aggregate(vec![Atomic(Effect::ALLOW, 3), Atomic(Effect::DENY, 2)]);
+        assert_eq!(effect.partially_resolve(&known), Atomic(Effect::ALLOW, 3));
+
+        let effect = Aggregate(vec![Atomic(Effect::ALLOW, 1), Silent]);
+        assert_eq!(effect.partially_resolve(&known), Fixed(Effect::ALLOW));
+
+        let effect = Aggregate(vec![Atomic(Effect::ALLOW, 2), Silent]);
+        assert_eq!(effect.partially_resolve(&known), Silent);
+    }
+
+    #[test]
+    fn test_disjoint() {
+        use DependentEffect::*;
+        let known = HashMap::from([(1, true), (2, false)]);
+
+        let effect = Disjoint(vec![Atomic(Effect::ALLOW, 3), Atomic(Effect::ALLOW, 2)]);
+        assert_eq!(effect.partially_resolve(&known), Silent);
+
+        let effect = Disjoint(vec![Atomic(Effect::DENY, 3), Atomic(Effect::ALLOW, 1)]);
+        assert_eq!(effect.partially_resolve(&known), Atomic(Effect::DENY, 3));
+
+        let effect = Disjoint(vec![Atomic(Effect::ALLOW, 1), Fixed(Effect::ALLOW)]);
+        assert_eq!(effect.partially_resolve(&known), Fixed(Effect::ALLOW));
+
+        // DENY does not decide a strict combination
+        let effect = Disjoint(vec![Atomic(Effect::DENY, 1), Atomic(Effect::ALLOW, 3)]);
+        assert_eq!(
+            effect.partially_resolve(&known),
+            Disjoint(vec![Fixed(Effect::DENY), Atomic(Effect::ALLOW, 3)])
+        );
+    }
+
+    #[test]
+    fn test_combined() {
+        use DependentEffect::*;
+        let known = HashMap::from([(1, true), (2, false)]);
+
+        // a decided leading constituent decides first-applicable
+        let effect = Combined(
+            Algorithm::FirstApplicable,
+            vec![
+                Atomic(Effect::ALLOW, 2),
+                Atomic(Effect::DENY, 1),
+                Atomic(Effect::ALLOW, 3),
+            ],
+        );
+        assert_eq!(effect.partially_resolve(&known), Fixed(Effect::DENY));
+
+        // an undecided constituent ahead of it does not
+        let effect = Combined(
+            Algorithm::FirstApplicable,
+            vec![Atomic(Effect::ALLOW, 3), Atomic(Effect::DENY, 1)],
+        );
+        assert_eq!(
+            effect.partially_resolve(&known),
+            Combined(
+                Algorithm::FirstApplicable,
+                vec![Atomic(Effect::ALLOW, 3), Fixed(Effect::DENY)]
+            )
+        );
+
+        let effect = Combined(
+            Algorithm::DenyUnlessPermit,
+            vec![Atomic(Effect::ALLOW, 2), Silent],
+        );
+        assert_eq!(effect.partially_resolve(&known), Fixed(Effect::DENY));
+    }
+
+    #[test]
+    fn test_prioritized() {
+        use DependentEffect::*;
+        let known = HashMap::from([(1, true), (2, false)]);
+
+        // a decided effect outranks lower priorities and silence is dropped
+        let effect = Prioritized(vec![
+            (3, Atomic(Effect::ALLOW, 3)),
+            (2, Atomic(Effect::ALLOW, 1)),
+            (1, Atomic(Effect::DENY, 4)),
+            (4, Atomic(Effect::DENY, 2)),
+        ]);
+        assert_eq!(
+            effect.partially_resolve(&known),
+            Prioritized(vec![
+                (3, Atomic(Effect::ALLOW, 3)),
+                (2, Fixed(Effect::ALLOW))
+            ])
+        );
+
+        // DENY at the highest remaining priority decides
+        let effect = Prioritized(vec![
+            (3, Atomic(Effect::ALLOW, 3)),
+            (3, Atomic(Effect::DENY, 1)),
+        ]);
+        assert_eq!(effect.partially_resolve(&known), Fixed(Effect::DENY));
+
+        // ALLOW does not decide against an undecided DENY of equal priority
+        let effect = Prioritized(vec![
+            (3, Atomic(Effect::DENY, 3)),
+            (3, Atomic(Effect::ALLOW, 1)),
+        ]);
+        assert_eq!(
+            effect.partially_resolve(&known),
+            Prioritized(vec![
+                (3, Atomic(Effect::DENY, 3)),
+                (3, Fixed(Effect::ALLOW))
+            ])
+        );
+
+        let effect = Prioritized(vec![
+            (3, Atomic(Effect::DENY, 2)),
+            (1, Atomic(Effect::ALLOW, 3)),
+        ]);
+        assert_eq!(effect.partially_resolve(&known), Atomic(Effect::ALLOW, 3));
+    }
+
+    #[test]
+    fn test_annotated() {
+        use crate::obligation::*;
+        use DependentEffect::*;
+        let known = HashMap::from([(1, true), (2, false)]);
+        let audit = Duties::new().with_obligation(Duty::new("audit"));
+        let obligated = |eff| Obligated(audit.clone(), Box::new(eff));
+
+        let effect = Aggregate(vec![
+            obligated(Atomic(Effect::DENY, 3)),
+            Atomic(Effect::ALLOW, 4),
+            Atomic(Effect::DENY, 1),
+        ]);
+        let residual = effect.partially_resolve(&known);
+        assert_eq!(
+            residual,
+            Aggregate(vec![
+                Fixed(Effect::DENY),
+                obligated(Atomic(Effect::DENY, 3))
+            ])
+        );
+        for bits in [1 << 1, 1 << 1 | 1 << 3] {
+            assert_eq!(
+                residual.resolve_with_duties(&Bits(bits)),
+                effect.resolve_with_duties(&Bits(bits))
+            );
+        }
+
+        assert_eq!(
+            obligated(Atomic(Effect::DENY, 2)).partially_resolve(&known),
+            Silent
+        );
+        assert_eq!(
+            obligated(Atomic(Effect::DENY, 1)).partially_resolve(&known),
+            obligated(Fixed(Effect::DENY))
+        );
+    }
+
+    #[test]
+    fn test_reliable_environment_decides_everything() {
+        for effect in effects(1) {
+            for bits in 0..8 {
+                let expected = match effect.resolve(&Bits(bits)).unwrap() {
+                    ALLOW => DependentEffect::Fixed(Effect::ALLOW),
+                    DENY => DependentEffect::Fixed(Effect::DENY),
+                    _ => DependentEffect::Silent,
+                };
+
+                assert_eq!(effect.partially_resolve(&Bits(bits)), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_residual_resolves_identically() {
+        for effect in effects(2) {
+            // each condition is known true, known false or unknown
+            for known in 0..27u32 {
+                let values: HashMap<u32, bool> = (0..3)
+                    .filter_map(|c| match known / 3u32.pow(c) % 3 {
+                        0 => Some((c, true)),
+                        1 => Some((c, false)),
+                        _ => None,
+                    })
+                    .collect();
+
+                let residual = effect.partially_resolve(&values);
+
+                for bits in 0..8 {
+                    let consistent = values.iter().all(|(c, v)| (bits & (1 << c) != 0) == *v);
+                    if consistent {
+                        assert_eq!(
+                            residual.resolve(&Bits(bits)),
+                            effect.resolve(&Bits(bits)),
+                            "{:?} with {:?}",
+                            effect,
+                            values
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,8 +1,43 @@
+//! Hierarchical resource paths and path matchers.
+//!
+//! # String syntax
+//!
+//! Paths and matchers are written as elements separated by `/`, e.g.
+//! `org/acme/bucket/logs`. Within an element, `\` escapes the character that
+//! follows it so `\/` is a literal separator and `\\` a literal backslash.
+//! Elements cannot be empty and the empty string is the empty path.
+//!
+//! Matcher elements are interpreted as follows:
+//!
+//! * `*` matches any single element (`PathElemMatcher::ANY`)
+//! * `**` matches zero or more elements (`PathElemMatcher::MULTI`)
+//! * `!` matches nothing (`PathElemMatcher::NONE`)
+//! * `{name}` matches any single element and captures it as `name`
+//!   (`PathElemMatcher::C(_)`). Names consist of letters, digits, `_` and `-`.
+//! * anything else is a glob pattern (see `authorization_core::glob`); patterns
+//!   without wildcards match exactly (`PathElemMatcher::V(_)`)
+//!
+//! Displaying a value and parsing the result produces an equal value, provided
+//! no element is empty.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+use super::glob::*;
 use super::matcher::*;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct PathElem(String);
 
+impl PathElem {
+    /// The element value.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
 impl<I> From<I> for PathElem
 where
     I: Into<String>,
@@ -12,11 +47,24 @@ where
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// Matches a single path element.
+///
+/// A `G(_)` whose pattern is `*` is equal to `ANY` and one whose pattern has no
+/// wildcards is equal to the corresponding `V(_)`.
+#[derive(Debug, Clone)]
 pub enum PathElemMatcher {
     ANY,
     NONE,
     V(String),
+    /// Matches elements conforming to a glob pattern e.g. `report-*`
+    G(Glob),
+    /// Matches zero or more consecutive elements when part of a `PathMatcher`.
+    /// Tested against a single element it behaves like `ANY`.
+    MULTI,
+    /// Matches any single element and binds it to a name. See
+    /// `PathMatcher::match_with_bindings`. Tested against a single element it
+    /// behaves like `ANY`. Use `capture` to construct it with a valid name.
+    C(String),
 }
 
 impl PathElemMatcher {
@@ -26,8 +74,67 @@ impl PathElemMatcher {
     {
         PathElemMatcher::V(v.into())
     }
+
+    /// Construct a matcher that captures any element as `name`. Fails with
+    /// `ParsePathError::InvalidCapture(0)` unless the name is non-empty and
+    /// consists of letters, digits, `_` and `-`.
+    pub fn capture<N>(name: N) -> Result<PathElemMatcher, ParsePathError>
+    where
+        N: Into<String>,
+    {
+        let name = name.into();
+        if is_capture_name(&name) {
+            Ok(PathElemMatcher::C(name))
+        } else {
+            Err(ParsePathError::InvalidCapture(0))
+        }
+    }
+
+    /// Private helper. Determine if the matcher always consumes exactly one
+    /// element and binds nothing.
+    fn is_single(&self) -> bool {
+        !matches!(self, PathElemMatcher::MULTI | PathElemMatcher::C(_))
+    }
+
+    /// Construct a matcher from a glob pattern. Patterns without wildcards
+    /// produce an exact `V(_)` matcher and `*` produces `ANY`.
+    pub fn glob(pattern: &str) -> Result<PathElemMatcher, GlobError> {
+        Ok(PathElemMatcher::from_glob(Glob::new(pattern)?))
+    }
+
+    /// Construct a matcher from a compiled glob, normalized as for `glob`.
+    pub fn from_glob(glob: Glob) -> PathElemMatcher {
+        PathElemMatcher::G(glob).canonical().into_owned()
+    }
+
+    /// Private helper. The normal form of the matcher, replacing a glob that
+    /// matches anything with `ANY` and one without wildcards with `V(_)`.
+    fn canonical(&self) -> Cow<'_, PathElemMatcher> {
+        match self {
+            PathElemMatcher::G(g) if g.is_any() => Cow::Owned(PathElemMatcher::ANY),
+            PathElemMatcher::G(g) => match g.as_literal() {
+                Some(v) => Cow::Owned(PathElemMatcher::V(v)),
+                None => Cow::Borrowed(self),
+            },
+            _ => Cow::Borrowed(self),
+        }
+    }
+}
+
+impl PartialEq for PathElemMatcher {
+    fn eq(&self, other: &Self) -> bool {
+        use PathElemMatcher::*;
+        match (self.canonical().as_ref(), other.canonical().as_ref()) {
+            (ANY, ANY) | (NONE, NONE) | (MULTI, MULTI) => true,
+            (V(a), V(b)) | (C(a), C(b)) => a == b,
+            (G(a), G(b)) => a == b,
+            _ => false,
+        }
+    }
 }
 
+impl Eq for PathElemMatcher {}
+
 impl<I> From<I> for PathElemMatcher
 where
     I: Into<String>,
@@ -49,9 +156,10 @@ impl Matcher for PathElemMatcher {
     fn test(&self, target: &Self::Target) -> bool {
         use PathElemMatcher::*;
         match self {
-            ANY => true,
+            ANY | MULTI | C(_) => true,
             NONE => false,
             V(s) => s == &target.0,
+            G(g) => g.test(&target.0),
         }
     }
 }
@@ -75,7 +183,13 @@ impl ExtendedMatcher for PathElemMatcher {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+impl GlobMatcher for PathElemMatcher {
+    fn match_glob(pattern: &str) -> Result<Self, GlobError> {
+        PathElemMatcher::glob(pattern)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct Path(Vec<PathElem>);
 
 impl Path {
@@ -86,6 +200,11 @@ impl Path {
     {
         Path(elems.into_iter().map(|e| e.into()).collect())
     }
+
+    /// The elements of the path in order.
+    pub fn elems(&self) -> &[PathElem] {
+        &self.0
+    }
 }
 
 impl<I, E> From<I> for Path
@@ -109,6 +228,11 @@ impl PathMatcher {
     {
         PathMatcher(elems.into_iter().map(|e| e.into()).collect())
     }
+
+    /// The element matchers in order.
+    pub fn elems(&self) -> &[PathElemMatcher] {
+        &self.0
+    }
 }
 
 impl<I, E> From<I> for PathMatcher
@@ -128,10 +252,358 @@ impl From<Path> for PathMatcher {
     }
 }
 
+/// Values bound by the capture elements of a `PathMatcher`, keyed by capture name.
+pub type Bindings = BTreeMap<String, String>;
+
 impl Matcher for PathMatcher {
     type Target = Path;
     fn test(&self, target: &Self::Target) -> bool {
-        self.0.len() == target.0.len() && self.0.iter().zip(target.0.iter()).all(|(m, e)| m.test(e))
+        if self.0.iter().all(|m| m.is_single()) {
+            return self.0.len() == target.0.len()
+                && self.0.iter().zip(target.0.iter()).all(|(m, e)| m.test(e));
+        }
+        self.match_with_bindings(target).is_some()
+    }
+}
+
+impl PathMatcher {
+    /// Match a path, producing the values bound by capture elements. The result
+    /// is `None` iff the path does not match. A name captured more than once must
+    /// bind the same value at every occurrence. If a path can be matched in more
+    /// than one way, the bindings of one of them are chosen deterministically.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use authorization_core::path::*;
+    ///
+    /// let matcher: PathMatcher = "users/{uid}/files/*".parse().unwrap();
+    ///
+    /// let bindings = matcher.match_with_bindings(&"users/42/files/a.txt".parse().unwrap());
+    /// assert_eq!(bindings.unwrap()["uid"], "42");
+    ///
+    /// let bindings = matcher.match_with_bindings(&"users/42/photos/a.jpg".parse().unwrap());
+    /// assert_eq!(bindings, None);
+    /// ```
+    pub fn match_with_bindings(&self, target: &Path) -> Option<Bindings> {
+        // Simulate the matcher as a nondeterministic automaton whose states are
+        // positions in the matcher. Every path element is visited once and every
+        // state at most once per element so matching is O(matcher * path) with no
+        // backtracking. Only when a capture name repeats can alternative bindings
+        // for the same state be significant and all of them are kept.
+        let pattern = &self.0;
+        let exhaustive = self.has_repeated_capture();
+        let mut states: Vec<Vec<Bindings>> = vec![vec![]; pattern.len() + 1];
+        states[0].push(Bindings::new());
+        self.skip_multi(&mut states, exhaustive);
+        for elem in &target.0 {
+            let mut next: Vec<Vec<Bindings>> = vec![vec![]; pattern.len() + 1];
+            for (i, m) in pattern.iter().enumerate() {
+                for bindings in &states[i] {
+                    let (to, bound) = match m {
+                        PathElemMatcher::MULTI => (i, Some(bindings.clone())),
+                        PathElemMatcher::C(name) => (i + 1, bind(bindings, name, &elem.0)),
+                        m if m.test(elem) => (i + 1, Some(bindings.clone())),
+                        _ => (i + 1, None),
+                    };
+                    if let Some(bound) = bound {
+                        add_state(&mut next[to], bound, exhaustive);
+                    }
+                }
+            }
+            self.skip_multi(&mut next, exhaustive);
+            if next.iter().all(|s| s.is_empty()) {
+                return None;
+            }
+            states = next;
+        }
+        states.pop().and_then(|s| s.into_iter().next())
+    }
+
+    /// Private helper. Every state at a `MULTI` can also proceed without consuming
+    /// an element.
+    fn skip_multi(&self, states: &mut [Vec<Bindings>], exhaustive: bool) {
+        for (i, m) in self.0.iter().enumerate() {
+            if *m == PathElemMatcher::MULTI {
+                for bindings in states[i].clone() {
+                    add_state(&mut states[i + 1], bindings, exhaustive);
+                }
+            }
+        }
+    }
+
+    /// Determine if any capture name is used more than once.
+    pub(crate) fn has_repeated_capture(&self) -> bool {
+        let mut names = BTreeSet::new();
+        self.0.iter().any(|m| match m {
+            PathElemMatcher::C(name) => !names.insert(name),
+            _ => false,
+        })
+    }
+}
+
+/// Private helper. Extend bindings with a capture, failing if the name is
+/// already bound to a different value.
+fn bind(bindings: &Bindings, name: &str, value: &str) -> Option<Bindings> {
+    match bindings.get(name) {
+        Some(bound) if bound != value => None,
+        Some(_) => Some(bindings.clone()),
+        None => {
+            let mut bindings = bindings.clone();
+            bindings.insert(name.into(), value.into());
+            Some(bindings)
+        }
+    }
+}
+
+/// Private helper. Record that a state is reachable with the given bindings.
+fn add_state(state: &mut Vec<Bindings>, bindings: Bindings, exhaustive: bool) {
+    if state.is_empty() || (exhaustive && !state.contains(&bindings)) {
+        state.push(bindings);
+    }
+}
+
+/// Error produced when parsing a path or path matcher from a string. Offsets
+/// are character offsets into the parsed string.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ParsePathError {
+    /// The element starting at the given offset is empty e.g. `a//b`.
+    EmptyElement(usize),
+    /// The input ends with an escape character.
+    TrailingEscape,
+    /// A separator was found at the given offset when parsing a single element.
+    UnexpectedSeparator(usize),
+    /// The matcher element starting at the given offset is not a valid glob pattern.
+    InvalidPattern(usize, GlobError),
+    /// The matcher element starting at the given offset is not a valid capture.
+    InvalidCapture(usize),
+}
+
+impl fmt::Display for ParsePathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ParsePathError::*;
+        match self {
+            EmptyElement(at) => write!(f, "empty path element at offset {}", at),
+            TrailingEscape => write!(f, "path ends with an escape character"),
+            UnexpectedSeparator(at) => write!(f, "unexpected separator at offset {}", at),
+            InvalidPattern(at, e) => {
+                write!(f, "invalid pattern in element at offset {}: {}", at, e)
+            }
+            InvalidCapture(at) => write!(f, "invalid capture in element at offset {}", at),
+        }
+    }
+}
+
+impl std::error::Error for ParsePathError {}
+
+const SEPARATOR: char = '/';
+const ESCAPE: char = '\\';
+
+/// Private helper. Split a string into raw (still escaped) elements, each
+/// paired with its starting offset.
+fn split_elements(s: &str) -> Result<Vec<(usize, String)>, ParsePathError> {
+    let mut elems = Vec::new();
+    if s.is_empty() {
+        return Ok(elems);
+    }
+    let mut start = 0;
+    let mut raw = String::new();
+    let mut chars = s.chars().enumerate();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            ESCAPE => {
+                let (_, escaped) = chars.next().ok_or(ParsePathError::TrailingEscape)?;
+                raw.push(ESCAPE);
+                raw.push(escaped);
+            }
+            SEPARATOR => {
+                elems.push((start, std::mem::take(&mut raw)));
+                start = i + 1;
+            }
+            c => raw.push(c),
+        }
+    }
+    elems.push((start, raw));
+    match elems.iter().find(|(_, raw)| raw.is_empty()) {
+        Some((at, _)) => Err(ParsePathError::EmptyElement(*at)),
+        None => Ok(elems),
+    }
+}
+
+/// Private helper. Remove escapes from a raw element.
+fn unescape(raw: &str) -> String {
+    let mut unescaped = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ESCAPE => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Private helper. Write a string escaping the characters in `special`.
+fn write_escaped(f: &mut fmt::Formatter<'_>, s: &str, special: &[char]) -> fmt::Result {
+    for c in s.chars() {
+        if special.contains(&c) {
+            write!(f, "{}", ESCAPE)?;
+        }
+        write!(f, "{}", c)?;
+    }
+    Ok(())
+}
+
+/// Private helper. Write a literal or glob matcher element, escaping a leading
+/// brace so it is not read back as a capture.
+fn write_matcher_elem(f: &mut fmt::Formatter<'_>, s: &str, special: &[char]) -> fmt::Result {
+    if s.starts_with('{') {
+        write!(f, "{}", ESCAPE)?;
+    }
+    write_escaped(f, s, special)
+}
+
+/// Private helper. Parse a single raw matcher element.
+fn parse_elem_matcher(at: usize, raw: &str) -> Result<PathElemMatcher, ParsePathError> {
+    match raw {
+        "*" => Ok(PathElemMatcher::ANY),
+        "**" => Ok(PathElemMatcher::MULTI),
+        "!" => Ok(PathElemMatcher::NONE),
+        raw if raw.starts_with('{') => raw
+            .strip_prefix('{')
+            .and_then(|r| r.strip_suffix('}'))
+            .and_then(|name| PathElemMatcher::capture(name).ok())
+            .ok_or(ParsePathError::InvalidCapture(at)),
+        raw => PathElemMatcher::glob(raw).map_err(|e| ParsePathError::InvalidPattern(at, e)),
+    }
+}
+
+/// Private helper. Determine if a string is a valid capture name.
+fn is_capture_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Private helper. Require a string to hold exactly one element.
+fn single_element(s: &str) -> Result<(usize, String), ParsePathError> {
+    let mut elems = split_elements(s)?;
+    match elems.len() {
+        0 => Err(ParsePathError::EmptyElement(0)),
+        1 => Ok(elems.remove(0)),
+        _ => Err(ParsePathError::UnexpectedSeparator(elems[1].0 - 1)),
+    }
+}
+
+impl fmt::Display for PathElem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_escaped(f, &self.0, &[ESCAPE, SEPARATOR])
+    }
+}
+
+impl FromStr for PathElem {
+    type Err = ParsePathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (_, raw) = single_element(s)?;
+        Ok(PathElem(unescape(&raw)))
+    }
+}
+
+impl fmt::Display for PathElemMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use PathElemMatcher::*;
+        match self.canonical().as_ref() {
+            ANY => write!(f, "*"),
+            MULTI => write!(f, "**"),
+            NONE => write!(f, "!"),
+            V(v) if v == "!" => write!(f, "{}!", ESCAPE),
+            V(v) => write_matcher_elem(f, v, &[ESCAPE, SEPARATOR, '*', '?', '[']),
+            G(g) => write_matcher_elem(f, &g.to_string(), &[SEPARATOR]),
+            C(name) => write!(f, "{{{}}}", name),
+        }
+    }
+}
+
+impl FromStr for PathElemMatcher {
+    type Err = ParsePathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (at, raw) = single_element(s)?;
+        parse_elem_matcher(at, &raw)
+    }
+}
+
+impl fmt::Display for Path {
+    /// # Examples
+    ///
+    /// ```
+    /// use authorization_core::path::*;
+    ///
+    /// let path = Path::new(vec!["org", "acme", "a/b"]);
+    ///
+    /// assert_eq!(path.to_string(), "org/acme/a\\/b");
+    /// assert_eq!(path.to_string().parse(), Ok(path));
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, e) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", SEPARATOR)?;
+            }
+            write!(f, "{}", e)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Path {
+    type Err = ParsePathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let elems = split_elements(s)?;
+        Ok(Path(
+            elems
+                .iter()
+                .map(|(_, raw)| PathElem(unescape(raw)))
+                .collect(),
+        ))
+    }
+}
+
+impl fmt::Display for PathMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, e) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", SEPARATOR)?;
+            }
+            write!(f, "{}", e)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PathMatcher {
+    type Err = ParsePathError;
+
+    /// # Examples
+    ///
+    /// ```
+    /// use authorization_core::matcher::*;
+    /// use authorization_core::path::*;
+    ///
+    /// let matcher: PathMatcher = "org/*/bucket/**".parse().unwrap();
+    ///
+    /// assert!(matcher.test(&"org/acme/bucket/logs/2020".parse().unwrap()));
+    /// assert!(!matcher.test(&"org/acme/queue/logs".parse().unwrap()));
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let elems = split_elements(s)?;
+        let elems: Result<Vec<PathElemMatcher>, ParsePathError> = elems
+            .iter()
+            .map(|(at, raw)| parse_elem_matcher(*at, raw))
+            .collect();
+        Ok(PathMatcher(elems?))
     }
 }
 
@@ -146,7 +618,7 @@ mod tests {
 
         let actual = e.test(&"totally arbitrary".into());
 
-        assert_eq!(actual, true);
+        assert!(actual);
     }
 
     #[test]
@@ -155,7 +627,7 @@ mod tests {
 
         let actual = e.test(&"totally arbitrary".into());
 
-        assert_eq!(actual, false);
+        assert!(!actual);
     }
 
     #[test]
@@ -163,10 +635,10 @@ mod tests {
         let matcher = PathElemMatcher::V("matchit".into());
 
         let actual = matcher.test(&"matchit".into());
-        assert_eq!(actual, true);
+        assert!(actual);
 
         let actual = matcher.test(&"arbitrary".into());
-        assert_eq!(actual, false);
+        assert!(!actual);
     }
 
     #[test]
@@ -206,6 +678,74 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_path_elem_matcher_glob() {
+        let matcher = PathElemMatcher::glob("report-*.pdf").unwrap();
+
+        assert!(matcher.test(&"report-2020.pdf".into()));
+        assert!(!matcher.test(&"report-2020.txt".into()));
+        assert!(!matcher.test(&"summary.pdf".into()));
+    }
+
+    #[test]
+    fn test_path_elem_matcher_glob_literal() {
+        let matcher = PathElemMatcher::glob("a\\*").unwrap();
+
+        assert_eq!(matcher, PathElemMatcher::new("a*"));
+    }
+
+    #[test]
+    fn test_path_elem_matcher_glob_normalized() {
+        let any = PathElemMatcher::G(Glob::new("*").unwrap());
+        let literal = PathElemMatcher::G(Glob::new("abc").unwrap());
+
+        assert_eq!(PathElemMatcher::glob("*"), Ok(PathElemMatcher::ANY));
+        assert!(matches!(
+            PathElemMatcher::from_glob(Glob::new("**").unwrap()),
+            PathElemMatcher::ANY
+        ));
+        assert_eq!(any, PathElemMatcher::ANY);
+        assert_eq!(literal, PathElemMatcher::new("abc"));
+        assert_ne!(any, PathElemMatcher::MULTI);
+        assert_eq!(any.to_string().parse(), Ok(any));
+        assert_eq!(literal.to_string().parse(), Ok(literal));
+    }
+
+    #[test]
+    fn test_path_elem_matcher_glob_error() {
+        let actual = PathElemMatcher::glob("v[0-9");
+
+        assert_eq!(actual, Err(GlobError::UnterminatedClass(1)));
+    }
+
+    #[test]
+    fn test_path_elem_ext_match_glob() {
+        let matcher = PathElemMatcher::match_glob("v?").unwrap();
+
+        assert_eq!(matcher, PathElemMatcher::glob("v?").unwrap());
+        assert!(matcher.test(&"v1".into()));
+        assert!(!matcher.test(&"v10".into()));
+    }
+
+    #[test]
+    fn test_path_match_with_glob() {
+        let matcher = PathMatcher::new(vec![
+            PathElemMatcher::new("reports"),
+            PathElemMatcher::glob("*.pdf").unwrap(),
+        ]);
+
+        assert!(matcher.test(&vec!["reports", "q1.pdf"].into()));
+        assert!(!matcher.test(&vec!["reports", "q1.doc"].into()));
+        assert!(!matcher.test(&vec!["other", "q1.pdf"].into()));
+    }
+
+    #[test]
+    fn test_path_elem_matcher_multi() {
+        let e = PathElemMatcher::MULTI;
+
+        assert!(e.test(&"totally arbitrary".into()));
+    }
+
     #[test]
     /// basic happy path
     fn test_path_match_all_exact() {
@@ -213,8 +753,8 @@ mod tests {
         let matcher: PathMatcher = positive.clone().into();
         let negative = Path::new(vec!["a", "b", "z"]);
 
-        assert_eq!(matcher.test(&positive), true);
-        assert_eq!(matcher.test(&negative), false);
+        assert!(matcher.test(&positive));
+        assert!(!matcher.test(&negative));
     }
 
     #[test]
@@ -229,9 +769,9 @@ mod tests {
         let p2 = vec!["a", "z", "c"].into();
         let p3 = vec!["z", "b", "c"].into();
 
-        assert_eq!(matcher.test(&p1), true);
-        assert_eq!(matcher.test(&p2), true);
-        assert_eq!(matcher.test(&p3), false);
+        assert!(matcher.test(&p1));
+        assert!(matcher.test(&p2));
+        assert!(!matcher.test(&p3));
     }
 
     #[test]
@@ -246,8 +786,309 @@ mod tests {
         let p2 = vec!["a", "b"].into();
         let p3 = vec!["a", "b", "c", "d"].into();
 
-        assert_eq!(matcher.test(&p1), true);
-        assert_eq!(matcher.test(&p2), false);
-        assert_eq!(matcher.test(&p3), false);
+        assert!(matcher.test(&p1));
+        assert!(!matcher.test(&p2));
+        assert!(!matcher.test(&p3));
+    }
+
+    #[test]
+    fn test_path_match_multi_suffix() {
+        let matcher = PathMatcher::new(vec![
+            PathElemMatcher::new("projects"),
+            PathElemMatcher::new("alpha"),
+            PathElemMatcher::MULTI,
+        ]);
+
+        assert!(matcher.test(&vec!["projects", "alpha"].into()));
+        assert!(matcher.test(&vec!["projects", "alpha", "x"].into()));
+        assert!(matcher.test(&vec!["projects", "alpha", "x", "y", "z"].into()));
+        assert!(!matcher.test(&vec!["projects", "beta", "x"].into()));
+        assert!(!matcher.test(&vec!["projects"].into()));
+    }
+
+    #[test]
+    fn test_path_match_multi_mid() {
+        let matcher = PathMatcher::new(vec![
+            PathElemMatcher::new("a"),
+            PathElemMatcher::MULTI,
+            PathElemMatcher::new("c"),
+        ]);
+
+        assert!(matcher.test(&vec!["a", "c"].into()));
+        assert!(matcher.test(&vec!["a", "b", "c"].into()));
+        assert!(matcher.test(&vec!["a", "c", "c"].into()));
+        assert!(matcher.test(&vec!["a", "x", "y", "c"].into()));
+        assert!(!matcher.test(&vec!["a", "x", "y"].into()));
+        assert!(!matcher.test(&vec!["a", "c", "d"].into()));
+        assert!(!matcher.test(&vec!["b", "c"].into()));
+    }
+
+    #[test]
+    fn test_path_match_multi_only() {
+        let matcher = PathMatcher::new(vec![PathElemMatcher::MULTI]);
+
+        assert!(matcher.test(&Path::new(Vec::<String>::new())));
+        assert!(matcher.test(&vec!["a", "b", "c"].into()));
+    }
+
+    #[test]
+    fn test_path_match_multi_combined_with_wild() {
+        let matcher = PathMatcher::new(vec![
+            PathElemMatcher::MULTI,
+            PathElemMatcher::ANY,
+            PathElemMatcher::glob("*.pdf").unwrap(),
+            PathElemMatcher::MULTI,
+        ]);
+
+        assert!(matcher.test(&vec!["x", "a.pdf"].into()));
+        assert!(matcher.test(&vec!["p", "q", "x", "a.pdf", "r"].into()));
+        assert!(!matcher.test(&vec!["a.pdf"].into()));
+        assert!(!matcher.test(&vec!["x", "y", "z"].into()));
+    }
+
+    #[test]
+    fn test_path_match_multi_deep_path_is_fast() {
+        let mut elems = vec![PathElemMatcher::MULTI];
+        for _ in 0..20 {
+            elems.push(PathElemMatcher::new("a"));
+            elems.push(PathElemMatcher::MULTI);
+        }
+        elems.push(PathElemMatcher::new("b"));
+        let matcher = PathMatcher::new(elems);
+        let path = Path::new(vec!["a"; 5_000]);
+
+        assert!(!matcher.test(&path));
+    }
+
+    #[test]
+    fn test_parse_path() {
+        let actual: Result<Path, _> = "org/acme/bucket/logs".parse();
+
+        assert_eq!(actual, Ok(vec!["org", "acme", "bucket", "logs"].into()));
+    }
+
+    #[test]
+    fn test_parse_path_escapes() {
+        let actual: Result<Path, _> = "a\\/b/c\\\\/*".parse();
+
+        assert_eq!(actual, Ok(vec!["a/b", "c\\", "*"].into()));
+    }
+
+    #[test]
+    fn test_parse_path_empty() {
+        let actual: Result<Path, _> = "".parse();
+
+        assert_eq!(actual, Ok(Path::new(Vec::<String>::new())));
+    }
+
+    #[test]
+    fn test_parse_path_errors() {
+        assert_eq!("a//b".parse::<Path>(), Err(ParsePathError::EmptyElement(2)));
+        assert_eq!("/a".parse::<Path>(), Err(ParsePathError::EmptyElement(0)));
+        assert_eq!("a/".parse::<Path>(), Err(ParsePathError::EmptyElement(2)));
+        assert_eq!("a\\".parse::<Path>(), Err(ParsePathError::TrailingEscape));
+    }
+
+    #[test]
+    fn test_parse_path_elem() {
+        assert_eq!("a\\/b".parse(), Ok(PathElem::from("a/b")));
+        assert_eq!(
+            "a/b".parse::<PathElem>(),
+            Err(ParsePathError::UnexpectedSeparator(1))
+        );
+        assert_eq!("".parse::<PathElem>(), Err(ParsePathError::EmptyElement(0)));
+    }
+
+    #[test]
+    fn test_parse_path_elem_matcher() {
+        assert_eq!("*".parse(), Ok(PathElemMatcher::ANY));
+        assert_eq!("**".parse(), Ok(PathElemMatcher::MULTI));
+        assert_eq!("!".parse(), Ok(PathElemMatcher::NONE));
+        assert_eq!("abc".parse(), Ok(PathElemMatcher::new("abc")));
+        assert_eq!("\\!".parse(), Ok(PathElemMatcher::new("!")));
+        assert_eq!("\\*".parse(), Ok(PathElemMatcher::new("*")));
+        assert_eq!("a\\/b".parse(), Ok(PathElemMatcher::new("a/b")));
+        assert_eq!("v?".parse(), Ok(PathElemMatcher::glob("v?").unwrap()));
+    }
+
+    #[test]
+    fn test_parse_path_matcher() {
+        let actual: Result<PathMatcher, _> = "org/*/bucket/**".parse();
+
+        assert_eq!(
+            actual,
+            Ok(PathMatcher::new(vec![
+                PathElemMatcher::new("org"),
+                PathElemMatcher::ANY,
+                PathElemMatcher::new("bucket"),
+                PathElemMatcher::MULTI,
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_path_matcher_invalid_pattern() {
+        let actual = "a/b[c".parse::<PathMatcher>();
+
+        assert_eq!(
+            actual,
+            Err(ParsePathError::InvalidPattern(
+                2,
+                GlobError::UnterminatedClass(1)
+            ))
+        );
+        assert_eq!(
+            actual.unwrap_err().to_string(),
+            "invalid pattern in element at offset 2: unterminated character class at offset 1"
+        );
+    }
+
+    #[test]
+    fn test_display_path_round_trip() {
+        let paths: Vec<Path> = vec![
+            vec!["org", "acme"].into(),
+            vec!["a/b", "c\\d", "*", "!", "[x]"].into(),
+            Path::new(Vec::<String>::new()),
+        ];
+        for path in paths {
+            assert_eq!(path.to_string().parse(), Ok(path));
+        }
+    }
+
+    #[test]
+    fn test_display_path_matcher_round_trip() {
+        let matchers = vec![
+            PathMatcher::new(vec![
+                PathElemMatcher::ANY,
+                PathElemMatcher::MULTI,
+                PathElemMatcher::NONE,
+            ]),
+            PathMatcher::new(vec!["!", "*", "**", "a/b", "c\\d", "[x]", "?"]),
+            PathMatcher::new(vec![
+                PathElemMatcher::glob("report-*").unwrap(),
+                PathElemMatcher::glob("[!/a-z]?").unwrap(),
+                PathElemMatcher::glob("a\\*b*").unwrap(),
+            ]),
+        ];
+        for matcher in matchers {
+            assert_eq!(matcher.to_string().parse(), Ok(matcher));
+        }
+    }
+
+    #[test]
+    fn test_path_elem_matcher_capture() {
+        let e = PathElemMatcher::capture("uid").unwrap();
+
+        assert!(e.test(&"totally arbitrary".into()));
+    }
+
+    #[test]
+    fn test_path_match_capture() {
+        let matcher = PathMatcher::new(vec![
+            PathElemMatcher::new("users"),
+            PathElemMatcher::capture("uid").unwrap(),
+            PathElemMatcher::new("files"),
+            PathElemMatcher::ANY,
+        ]);
+
+        let actual = matcher.match_with_bindings(&vec!["users", "42", "files", "a"].into());
+        let expected: Bindings = vec![("uid".to_string(), "42".to_string())]
+            .into_iter()
+            .collect();
+        assert_eq!(actual, Some(expected));
+
+        assert!(matcher.test(&vec!["users", "42", "files", "a"].into()));
+        assert!(!matcher.test(&vec!["users", "42", "files"].into()));
+        assert_eq!(
+            matcher.match_with_bindings(&vec!["users", "42", "other", "a"].into()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_path_match_capture_without_captures() {
+        let matcher = PathMatcher::new(vec!["a", "b"]);
+
+        let actual = matcher.match_with_bindings(&vec!["a", "b"].into());
+
+        assert_eq!(actual, Some(Bindings::new()));
+    }
+
+    #[test]
+    fn test_path_match_capture_with_multi() {
+        let matcher: PathMatcher = "orgs/{org}/**/{file}".parse().unwrap();
+
+        let actual = matcher
+            .match_with_bindings(&"orgs/acme/a/b/c.txt".parse().unwrap())
+            .unwrap();
+
+        assert_eq!(actual["org"], "acme");
+        assert_eq!(actual["file"], "c.txt");
+        assert!(!matcher.test(&"orgs/acme".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_path_match_repeated_capture() {
+        let matcher: PathMatcher = "**/{x}/**/{x}".parse().unwrap();
+
+        let actual = matcher.match_with_bindings(&"a/b/c/b/d".parse().unwrap());
+        let expected: Bindings = vec![("x".to_string(), "b".to_string())]
+            .into_iter()
+            .collect();
+        assert_eq!(actual, None);
+
+        let actual = matcher.match_with_bindings(&"a/b/c/d/b".parse().unwrap());
+        assert_eq!(actual, Some(expected));
+
+        assert!(!matcher.test(&"a/b/c/d".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_parse_capture() {
+        assert_eq!(
+            "{uid}".parse(),
+            Ok(PathElemMatcher::capture("uid").unwrap())
+        );
+        assert_eq!("\\{uid}".parse(), Ok(PathElemMatcher::new("{uid}")));
+        assert_eq!(
+            "a/{u id}".parse::<PathMatcher>(),
+            Err(ParsePathError::InvalidCapture(2))
+        );
+        assert_eq!(
+            "{}".parse::<PathElemMatcher>(),
+            Err(ParsePathError::InvalidCapture(0))
+        );
+        assert_eq!(
+            "{uid".parse::<PathElemMatcher>(),
+            Err(ParsePathError::InvalidCapture(0))
+        );
+    }
+
+    #[test]
+    fn test_capture_invalid_name() {
+        assert_eq!(
+            PathElemMatcher::capture("a/b"),
+            Err(ParsePathError::InvalidCapture(0))
+        );
+        assert_eq!(
+            PathElemMatcher::capture(""),
+            Err(ParsePathError::InvalidCapture(0))
+        );
+        assert_eq!(
+            PathElemMatcher::capture("user_id-2"),
+            Ok(PathElemMatcher::C("user_id-2".into()))
+        );
+    }
+
+    #[test]
+    fn test_display_capture_round_trip() {
+        let matcher = PathMatcher::new(vec![
+            PathElemMatcher::capture("uid").unwrap(),
+            PathElemMatcher::new("{uid}"),
+            PathElemMatcher::glob("{*}").unwrap(),
+        ]);
+
+        assert_eq!(matcher.to_string(), "{uid}/\\{uid}/\\{*}");
+        assert_eq!(matcher.to_string().parse(), Ok(matcher));
     }
 }
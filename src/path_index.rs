@@ -0,0 +1,327 @@
+//! Index for matching a path against many path matchers at once.
+//!
+//! The index is a trie keyed by `PathElemMatcher`. Looking up a concrete path
+//! follows exact elements by hash lookup and only visits the wildcard branches
+//! that exist along the way, so the cost depends on the shape of the matchers
+//! sharing a prefix with the path rather than on the total number of matchers.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::glob::Glob;
+use super::matcher::*;
+use super::path::*;
+
+/// Trie node. Children are indices into the index arena.
+#[derive(Debug, Clone, Default)]
+struct Node {
+    exact: HashMap<String, usize>,
+    any: Option<usize>,
+    globs: Vec<(Glob, usize)>,
+    multi: Option<usize>,
+    entries: Vec<usize>,
+}
+
+/// Indexed matcher and value.
+#[derive(Debug, Clone)]
+struct Entry<T> {
+    matcher: PathMatcher,
+    value: T,
+    /// The trie ignores capture names, so a matcher repeating a capture name
+    /// is tested against the path after lookup.
+    verify: bool,
+}
+
+/// A collection of path matchers, each with an attached value (e.g. a policy id),
+/// that can be queried for every matcher matching a concrete path.
+///
+/// # Examples
+///
+/// ```
+/// use authorization_core::path_index::*;
+///
+/// let mut index = PathIndex::new();
+/// index.insert("org/acme/**".parse().unwrap(), "p1");
+/// index.insert("org/*/bucket".parse().unwrap(), "p2");
+/// index.insert("org/other".parse().unwrap(), "p3");
+///
+/// let actual = index.matching(&"org/acme/bucket".parse().unwrap());
+///
+/// assert_eq!(actual, vec![&"p1", &"p2"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PathIndex<T> {
+    nodes: Vec<Node>,
+    entries: Vec<Entry<T>>,
+}
+
+impl<T> Default for PathIndex<T> {
+    fn default() -> Self {
+        PathIndex {
+            nodes: vec![Node::default()],
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<T> PathIndex<T> {
+    /// Create an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of matchers in the index.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Determine if the index holds no matchers.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Add a matcher and its associated value. Matchers containing `NONE` can
+    /// never match and are retained but not indexed.
+    pub fn insert(&mut self, matcher: PathMatcher, value: T) {
+        let id = self.entries.len();
+        if !matcher.elems().contains(&PathElemMatcher::NONE) {
+            let mut node = 0;
+            for elem in matcher.elems() {
+                node = self.child(node, elem);
+            }
+            self.nodes[node].entries.push(id);
+        }
+        let verify = matcher.has_repeated_capture();
+        self.entries.push(Entry {
+            matcher,
+            value,
+            verify,
+        });
+    }
+
+    /// Private helper. Find or create the child of a node for an element matcher.
+    fn child(&mut self, node: usize, elem: &PathElemMatcher) -> usize {
+        use PathElemMatcher::*;
+
+        let existing = match elem {
+            V(v) => self.nodes[node].exact.get(v).copied(),
+            ANY | C(_) => self.nodes[node].any,
+            MULTI => self.nodes[node].multi,
+            G(g) => self.nodes[node]
+                .globs
+                .iter()
+                .find(|(glob, _)| glob == g)
+                .map(|(_, child)| *child),
+            NONE => unreachable!("matchers containing NONE are not indexed"),
+        };
+        if let Some(child) = existing {
+            return child;
+        }
+
+        let child = self.nodes.len();
+        self.nodes.push(Node::default());
+        let parent = &mut self.nodes[node];
+        match elem {
+            V(v) => {
+                parent.exact.insert(v.clone(), child);
+            }
+            ANY | C(_) => parent.any = Some(child),
+            MULTI => parent.multi = Some(child),
+            G(g) => parent.globs.push((g.clone(), child)),
+            NONE => unreachable!("matchers containing NONE are not indexed"),
+        }
+        child
+    }
+
+    /// Find every matcher that matches a path, returning the matchers and their
+    /// values in insertion order.
+    pub fn matching_entries(&self, path: &Path) -> Vec<(&PathMatcher, &T)> {
+        let elems = path.elems();
+        let mut found = BTreeSet::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![(0, 0)];
+
+        while let Some((node, depth)) = pending.pop() {
+            if !visited.insert((node, depth)) {
+                continue;
+            }
+            let n = &self.nodes[node];
+            if let Some(multi) = n.multi {
+                pending.extend((depth..=elems.len()).map(|d| (multi, d)));
+            }
+            match elems.get(depth) {
+                None => found.extend(n.entries.iter().copied()),
+                Some(elem) => {
+                    let next = depth + 1;
+                    pending.extend(n.exact.get(elem.as_str()).map(|c| (*c, next)));
+                    pending.extend(n.any.map(|c| (c, next)));
+                    pending.extend(
+                        n.globs
+                            .iter()
+                            .filter(|(glob, _)| glob.test(elem.as_str()))
+                            .map(|(_, c)| (*c, next)),
+                    );
+                }
+            }
+        }
+
+        found
+            .into_iter()
+            .map(|id| &self.entries[id])
+            .filter(|e| !e.verify || e.matcher.test(path))
+            .map(|e| (&e.matcher, &e.value))
+            .collect()
+    }
+
+    /// Find the values of every matcher that matches a path, in insertion order.
+    pub fn matching(&self, path: &Path) -> Vec<&T> {
+        self.matching_entries(path)
+            .into_iter()
+            .map(|(_, v)| v)
+            .collect()
+    }
+
+    /// Iterate over all matchers and values in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&PathMatcher, &T)> {
+        self.entries.iter().map(|e| (&e.matcher, &e.value))
+    }
+}
+
+impl<T> FromIterator<(PathMatcher, T)> for PathIndex<T> {
+    fn from_iter<I: IntoIterator<Item = (PathMatcher, T)>>(iter: I) -> Self {
+        let mut index = PathIndex::new();
+        for (matcher, value) in iter {
+            index.insert(matcher, value);
+        }
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn matcher(s: &str) -> PathMatcher {
+        s.parse().unwrap()
+    }
+
+    fn path(s: &str) -> Path {
+        s.parse().unwrap()
+    }
+
+    fn index(matchers: &[&str]) -> PathIndex<usize> {
+        matchers
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (matcher(m), i))
+            .collect()
+    }
+
+    /// Compare index lookup with testing every matcher in turn.
+    fn check(matchers: &[&str], p: &str) {
+        let index = index(matchers);
+        let p = path(p);
+
+        let actual: Vec<usize> = index.matching(&p).into_iter().copied().collect();
+
+        let expected: Vec<usize> = matchers
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| matcher(m).test(&p))
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_empty_index() {
+        let index = PathIndex::<()>::new();
+
+        assert!(index.is_empty());
+        assert!(index.matching(&path("a/b")).is_empty());
+    }
+
+    #[test]
+    fn test_exact() {
+        let matchers = ["a/b/c", "a/b", "a/b/d", "x/b/c"];
+        check(&matchers, "a/b/c");
+        check(&matchers, "a/b");
+        check(&matchers, "a");
+        check(&matchers, "");
+    }
+
+    #[test]
+    fn test_any_and_capture() {
+        let matchers = ["a/*/c", "a/{x}/c", "*/*/*", "a/b/c", "a/*"];
+        check(&matchers, "a/b/c");
+        check(&matchers, "a/z/c");
+        check(&matchers, "q/z/c");
+        check(&matchers, "a/z");
+    }
+
+    #[test]
+    fn test_none() {
+        let index = index(&["a/!", "a/*"]);
+
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.matching(&path("a/b")), vec![&1]);
+    }
+
+    #[test]
+    fn test_glob() {
+        let matchers = [
+            "reports/*.pdf",
+            "reports/*.txt",
+            "reports/q?.pdf",
+            "reports/*",
+        ];
+        check(&matchers, "reports/q1.pdf");
+        check(&matchers, "reports/q10.pdf");
+        check(&matchers, "reports/a.txt");
+    }
+
+    #[test]
+    fn test_multi() {
+        let matchers = [
+            "**",
+            "a/**",
+            "a/**/c",
+            "a/**/c/**",
+            "**/c",
+            "a/b/**/**/c",
+            "b/**",
+        ];
+        check(&matchers, "");
+        check(&matchers, "a");
+        check(&matchers, "a/c");
+        check(&matchers, "a/b/c");
+        check(&matchers, "a/b/c/d/c");
+        check(&matchers, "a/b/c/d");
+        check(&matchers, "b/c");
+    }
+
+    #[test]
+    fn test_repeated_capture() {
+        let matchers = ["{x}/{x}", "{x}/{y}"];
+        check(&matchers, "a/a");
+        check(&matchers, "a/b");
+    }
+
+    #[test]
+    fn test_duplicate_matchers() {
+        let matchers = ["a/b", "a/b", "a/**", "a/**"];
+        check(&matchers, "a/b");
+    }
+
+    #[test]
+    fn test_matching_entries() {
+        let index: PathIndex<&str> = vec![(matcher("a/*"), "p1"), (matcher("b/*"), "p2")]
+            .into_iter()
+            .collect();
+
+        let actual = index.matching_entries(&path("a/b"));
+
+        assert_eq!(actual, vec![(&matcher("a/*"), &"p1")]);
+        assert_eq!(index.iter().count(), 2);
+    }
+}
@@ -1,8 +1,11 @@
 //! Policy configurations.
 
+use super::action::ActionMatch;
 use super::dependent_effect::*;
 use super::effect::*;
-use super::matcher::*;
+use super::metadata::Metadata;
+use super::obligation::Duties;
+use super::resource::ResourceMatch;
 
 /// A configured authorization policy.
 ///
@@ -18,21 +21,73 @@ pub enum Policy<RMatch, AMatch, CExp> {
 
     /// Always applies. It evaluates to `ConditionalEffect::Aggregate(_)`.
     Aggregate(Vec<Policy<RMatch, AMatch, CExp>>),
+
+    /// Always applies. It evaluates to `DependentEffect::Combined(_, _)`, combining
+    /// its constituents in order using the algorithm.
+    Combined(Algorithm, Vec<Policy<RMatch, AMatch, CExp>>),
+
+    /// Always applies. It evaluates to `DependentEffect::Prioritized(_)`, in which the
+    /// highest-priority constituents that apply and whose conditions hold decide
+    /// the effect.
+    Prioritized(Vec<(Priority, Policy<RMatch, AMatch, CExp>)>),
+
+    /// Applies if the wrapped policy applies. It evaluates to
+    /// `DependentEffect::Obligated(_, _)`, attaching obligations and advice to the
+    /// effect of the wrapped policy.
+    Obligated(Duties, Box<Policy<RMatch, AMatch, CExp>>),
+
+    /// Applies if the wrapped policy applies. It evaluates to
+    /// `DependentEffect::Identified(_, _)` if the metadata has an id, so resolution
+    /// can report the statement, and to the effect of the wrapped policy otherwise.
+    Annotated(Metadata, Box<Policy<RMatch, AMatch, CExp>>),
+}
+
+impl<RMatch, AMatch, CExp> Policy<RMatch, AMatch, CExp> {
+    /// The metadata of an annotated policy.
+    pub fn metadata(&self) -> Option<&Metadata> {
+        match self {
+            Policy::Annotated(metadata, _) => Some(metadata),
+            _ => None,
+        }
+    }
+
+    /// Find the annotated policy with an id, searching depth first.
+    pub fn find(&self, id: &str) -> Option<&Policy<RMatch, AMatch, CExp>> {
+        use Policy::*;
+        match self {
+            Unconditional(_, _, _) | Conditional(_, _, _, _) => None,
+            Aggregate(ps) | Combined(_, ps) => ps.iter().find_map(|p| p.find(id)),
+            Prioritized(ps) => ps.iter().find_map(|(_, p)| p.find(id)),
+            Obligated(_, p) => p.find(id),
+            Annotated(metadata, p) => {
+                if metadata.id.as_deref() == Some(id) {
+                    Some(self)
+                } else {
+                    p.find(id)
+                }
+            }
+        }
+    }
 }
 
 impl<R, RMatch, A, AMatch, CExp> Policy<RMatch, AMatch, CExp>
 where
-    RMatch: Matcher<Target = R>,
-    AMatch: Matcher<Target = A>,
+    RMatch: ResourceMatch<Resource = R>,
+    AMatch: ActionMatch<Action = A>,
 {
     /// Private helper
     fn applies(&self, resource: &R, action: &A) -> bool {
         use Policy::*;
 
         match self {
-            Conditional(rmatch, amatch, _, _) => rmatch.test(&resource) && amatch.test(&action),
-            Unconditional(rmatch, amatch, _) => rmatch.test(&resource) && amatch.test(&action),
-            Aggregate(_) => true,
+            Conditional(rmatch, amatch, _, _) => {
+                rmatch.test_resource(resource) && amatch.test_action(action)
+            }
+            Unconditional(rmatch, amatch, _) => {
+                rmatch.test_resource(resource) && amatch.test_action(action)
+            }
+            Aggregate(_) | Combined(_, _) | Prioritized(_) => true,
+            Obligated(_, policy) | Annotated(_, policy) => policy.applies(resource, action),
         }
     }
 
@@ -48,6 +103,80 @@ where
                 Aggregate(ts) => DependentEffect::Aggregate(
                     ts.into_iter().map(|t| t.apply(resource, action)).collect(),
                 ),
+                Combined(algorithm, ts) => DependentEffect::Combined(
+                    algorithm,
+                    ts.into_iter().map(|t| t.apply(resource, action)).collect(),
+                ),
+                Prioritized(ts) => DependentEffect::Prioritized(
+                    ts.into_iter()
+                        .map(|(priority, t)| (priority, t.apply(resource, action)))
+                        .collect(),
+                ),
+                Obligated(duties, t) => {
+                    DependentEffect::Obligated(duties, Box::new(t.apply(resource, action)))
+                }
+                Annotated(Metadata { id: Some(id), .. }, t) => {
+                    DependentEffect::Identified(id, Box::new(t.apply(resource, action)))
+                }
+                Annotated(_, t) => t.apply(resource, action),
+            }
+        } else {
+            DependentEffect::Silent
+        }
+    }
+
+    /// Apply policy to a concrete resource and action without consuming it. The
+    /// resulting `DependentEffect` borrows the conditions of the policy, so a
+    /// long-lived policy can be applied to many requests, concurrently if the
+    /// policy is `Sync`. Duties and statement ids are copied into the result.
+    ///
+    /// The result can be resolved in an environment for the borrowed conditions,
+    /// e.g. `authorization_core::environment::Borrowed`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use authorization_core::dependent_effect::*;
+    /// use authorization_core::effect::*;
+    /// use authorization_core::environment::*;
+    /// use authorization_core::policy::*;
+    /// use authorization_core::resource::*;
+    /// use authorization_core::action::*;
+    ///
+    /// let policy = Policy::Conditional(StrResource("r"), StrAction("a"), Effect::ALLOW, ());
+    ///
+    /// let effect = policy.apply_ref(&"r".into(), &"a".into());
+    ///
+    /// assert_eq!(effect, DependentEffect::Atomic(Effect::ALLOW, &()));
+    /// assert_eq!(effect.resolve(&Borrowed(&PositiveEnvironment)), Ok(ALLOW));
+    /// ```
+    pub fn apply_ref(&self, resource: &R, action: &A) -> DependentEffect<&CExp> {
+        use Policy::*;
+
+        if self.applies(resource, action) {
+            match self {
+                Conditional(_, _, eff, cond) => DependentEffect::Atomic(*eff, cond),
+                Unconditional(_, _, eff) => DependentEffect::Fixed(*eff),
+                Aggregate(ts) => DependentEffect::Aggregate(
+                    ts.iter().map(|t| t.apply_ref(resource, action)).collect(),
+                ),
+                Combined(algorithm, ts) => DependentEffect::Combined(
+                    algorithm.clone(),
+                    ts.iter().map(|t| t.apply_ref(resource, action)).collect(),
+                ),
+                Prioritized(ts) => DependentEffect::Prioritized(
+                    ts.iter()
+                        .map(|(priority, t)| (*priority, t.apply_ref(resource, action)))
+                        .collect(),
+                ),
+                Obligated(duties, t) => DependentEffect::Obligated(
+                    duties.clone(),
+                    Box::new(t.apply_ref(resource, action)),
+                ),
+                Annotated(Metadata { id: Some(id), .. }, t) => {
+                    DependentEffect::Identified(id.clone(), Box::new(t.apply_ref(resource, action)))
+                }
+                Annotated(_, t) => t.apply_ref(resource, action),
             }
         } else {
             DependentEffect::Silent
@@ -55,23 +184,60 @@ where
     }
 }
 
+/// A policy that can be applied to a concrete resource and action: either an
+/// owned `Policy`, which is consumed, or a borrowed `&Policy`, whose conditions
+/// are borrowed by the result.
+pub trait Applicable<R, A> {
+    /// The type of conditional expression in the resulting dependent effect.
+    type CExp;
+
+    /// Apply to a concrete resource and action.
+    fn apply_to(self, resource: &R, action: &A) -> DependentEffect<Self::CExp>;
+}
+
+impl<R, RMatch, A, AMatch, CExp> Applicable<R, A> for Policy<RMatch, AMatch, CExp>
+where
+    RMatch: ResourceMatch<Resource = R>,
+    AMatch: ActionMatch<Action = A>,
+{
+    type CExp = CExp;
+
+    fn apply_to(self, resource: &R, action: &A) -> DependentEffect<CExp> {
+        self.apply(resource, action)
+    }
+}
+
+impl<'p, R, RMatch, A, AMatch, CExp> Applicable<R, A> for &'p Policy<RMatch, AMatch, CExp>
+where
+    RMatch: ResourceMatch<Resource = R>,
+    AMatch: ActionMatch<Action = A>,
+{
+    type CExp = &'p CExp;
+
+    fn apply_to(self, resource: &R, action: &A) -> DependentEffect<&'p CExp> {
+        self.apply_ref(resource, action)
+    }
+}
+
 /// Apply multiple policies using a strict algorithm. This is used when evaluating
 /// policies for a composite principal (e.g. application + user) where authorization
 /// requires all consitutents to be authorized.
-pub fn apply_disjoint<R, A, Iter, CExp, RMatch, AMatch>(
+///
+/// The policies can be owned or borrowed. Borrowed policies are applied with
+/// `Policy::apply_ref` so the result borrows their conditions.
+pub fn apply_disjoint<R, A, Iter>(
     policies: Iter,
     resource: &R,
     action: &A,
-) -> DependentEffect<CExp>
+) -> DependentEffect<<Iter::Item as Applicable<R, A>>::CExp>
 where
-    Iter: IntoIterator<Item = Policy<RMatch, AMatch, CExp>>,
-    RMatch: Matcher<Target = R>,
-    AMatch: Matcher<Target = A>,
+    Iter: IntoIterator,
+    Iter::Item: Applicable<R, A>,
 {
     DependentEffect::Disjoint(
         policies
             .into_iter()
-            .map(|p| p.apply(resource, action))
+            .map(|p| p.apply_to(resource, action))
             .collect(),
     )
 }
@@ -81,11 +247,10 @@ mod tests {
 
     use super::*;
     use crate::action::*;
+    use crate::environment::*;
+    use crate::matcher::*;
     use crate::resource::*;
 
-    #[derive(Clone, Copy)]
-    pub struct Matcher(&'static str);
-
     static MATCH_R: StrResource = StrResource("r");
     static MISS_R: StrResource = StrResource("miss");
     static MATCH_A: StrAction = StrAction("a");
@@ -136,6 +301,92 @@ mod tests {
         assert_eq!(actual, DependentEffect::Atomic(Effect::ALLOW, ()));
     }
 
+    #[test]
+    fn test_combined_matchers() {
+        let rmatch = And(
+            Or(StrResource("r1"), StrResource("r2")),
+            Not(StrResource("r2")),
+        );
+        let amatch = AnyOf(vec![StrAction("read"), StrAction("list")]);
+        let policy = Policy::<_, _, ()>::Unconditional(rmatch, amatch, Effect::ALLOW);
+
+        let actual = policy.clone().apply(&"r1".into(), &"list".into());
+        assert_eq!(actual, DependentEffect::Fixed(Effect::ALLOW));
+
+        let actual = policy.clone().apply(&"r2".into(), &"list".into());
+        assert_eq!(actual, DependentEffect::Silent);
+
+        let actual = policy.apply(&"r1".into(), &"write".into());
+        assert_eq!(actual, DependentEffect::Silent);
+    }
+
+    #[test]
+    fn test_shared_matchers() {
+        use std::rc::Rc;
+
+        let rmatch: Rc<dyn Matcher<Target = StrResource>> = Rc::new(MATCH_R);
+        let policy = Policy::Aggregate(vec![
+            Policy::Unconditional(rmatch.clone(), &MATCH_A, Effect::ALLOW),
+            Policy::Conditional(rmatch, &MATCH_A, Effect::DENY, ()),
+        ]);
+
+        let actual = policy.apply(&"r".into(), &"a".into());
+
+        assert_eq!(
+            actual,
+            DependentEffect::Aggregate(vec![
+                DependentEffect::Fixed(Effect::ALLOW),
+                DependentEffect::Atomic(Effect::DENY, ()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_owned_matchers() {
+        let policy = {
+            let config = String::from("r a");
+            let mut parts = config.split(' ');
+            let rmatch = StringResource::from(parts.next().unwrap());
+            let amatch = StringAction::from(parts.next().unwrap());
+            Policy::<_, _, ()>::Unconditional(rmatch, amatch, Effect::ALLOW)
+        };
+
+        let actual = policy.apply(&"r".into(), &"a".into());
+
+        assert_eq!(actual, DependentEffect::Fixed(Effect::ALLOW));
+    }
+
+    #[test]
+    fn test_direct_action_and_resource_match() {
+        /// Implements only the policy-specific traits, not `Matcher`
+        struct Prefix(&'static str);
+
+        impl ResourceMatch for Prefix {
+            type Resource = StringResource;
+
+            fn test_resource(&self, resource: &Self::Resource) -> bool {
+                resource.as_str().starts_with(self.0)
+            }
+        }
+
+        impl ActionMatch for Prefix {
+            type Action = StringAction;
+
+            fn test_action(&self, action: &Self::Action) -> bool {
+                action.as_str().starts_with(self.0)
+            }
+        }
+
+        let policy = Policy::<_, _, ()>::Unconditional(Prefix("r"), Prefix("a"), Effect::DENY);
+
+        let actual = policy.apply(&"r1".into(), &"a1".into());
+        assert_eq!(actual, DependentEffect::Fixed(Effect::DENY));
+
+        let policy = Policy::<_, _, ()>::Unconditional(Prefix("r"), Prefix("a"), Effect::DENY);
+        let actual = policy.apply(&"x1".into(), &"a1".into());
+        assert_eq!(actual, DependentEffect::Silent);
+    }
+
     #[test]
     fn test_aggregate() {
         let match_r1: StrResource = "r1".into();
@@ -176,6 +427,260 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_combined() {
+        let policy = Policy::Combined(
+            Algorithm::FirstApplicable,
+            vec![
+                Policy::Conditional(MISS_R, MATCH_A, Effect::DENY, 1),
+                Policy::Conditional(MATCH_R, MATCH_A, Effect::ALLOW, 2),
+                Policy::Unconditional(MATCH_R, MATCH_A, Effect::DENY),
+            ],
+        );
+
+        let actual = policy.apply(&"r".into(), &"a".into());
+
+        assert_eq!(
+            actual,
+            DependentEffect::Combined(
+                Algorithm::FirstApplicable,
+                vec![
+                    DependentEffect::Silent,
+                    DependentEffect::Atomic(Effect::ALLOW, 2),
+                    DependentEffect::Fixed(Effect::DENY),
+                ]
+            )
+        );
+        assert_eq!(actual.resolve(&2), Ok(ALLOW));
+        assert_eq!(actual.resolve(&3), Ok(DENY));
+    }
+
+    #[test]
+    fn test_custom_algorithm() {
+        /// Last applicable constituent wins.
+        struct LastApplicable;
+
+        impl CombiningAlgorithm for LastApplicable {
+            fn combine(&self, effs: &[ComputedEffect]) -> ComputedEffect {
+                effs.iter()
+                    .rev()
+                    .find(|e| **e != SILENT)
+                    .copied()
+                    .unwrap_or(SILENT)
+            }
+        }
+
+        let algorithm = Algorithm::Custom(CustomAlgorithm::new(LastApplicable));
+        let policy = Policy::Aggregate(vec![Policy::Combined(
+            algorithm.clone(),
+            vec![
+                Policy::Unconditional(MATCH_R, MATCH_A, Effect::DENY),
+                Policy::Conditional(MATCH_R, MATCH_A, Effect::ALLOW, 2),
+            ],
+        )]);
+
+        let actual = policy.apply(&"r".into(), &"a".into());
+
+        assert_eq!(
+            actual,
+            DependentEffect::Aggregate(vec![DependentEffect::Combined(
+                algorithm,
+                vec![
+                    DependentEffect::Fixed(Effect::DENY),
+                    DependentEffect::Atomic(Effect::ALLOW, 2),
+                ]
+            )])
+        );
+        assert_eq!(actual.resolve(&2), Ok(ALLOW));
+        assert_eq!(actual.resolve(&3), Ok(DENY));
+        assert_eq!(actual.resolve_short_circuit(&2).unwrap().effect, ALLOW);
+        assert_eq!(actual.resolve_explained(&2).unwrap().0, ALLOW);
+    }
+
+    #[test]
+    fn test_prioritized() {
+        let policy = Policy::Prioritized(vec![
+            (0, Policy::Unconditional(MATCH_R, MATCH_A, Effect::DENY)),
+            (10, Policy::Conditional(MATCH_R, MATCH_A, Effect::ALLOW, 1)),
+            (20, Policy::Conditional(MISS_R, MATCH_A, Effect::DENY, 1)),
+        ]);
+
+        let actual = policy.apply(&"r".into(), &"a".into());
+
+        assert_eq!(
+            actual,
+            DependentEffect::Prioritized(vec![
+                (0, DependentEffect::Fixed(Effect::DENY)),
+                (10, DependentEffect::Atomic(Effect::ALLOW, 1)),
+                (20, DependentEffect::Silent),
+            ])
+        );
+        // an emergency ALLOW overrides the general DENY
+        assert_eq!(actual.resolve(&1), Ok(ALLOW));
+        assert_eq!(actual.resolve(&2), Ok(DENY));
+    }
+
+    #[test]
+    fn test_prioritized_in_aggregate() {
+        let policy = Policy::Aggregate(vec![
+            Policy::Prioritized(vec![
+                (0, Policy::Unconditional(MATCH_R, MATCH_A, Effect::DENY)),
+                (10, Policy::Conditional(MATCH_R, MATCH_A, Effect::ALLOW, 1)),
+            ]),
+            Policy::Conditional(MATCH_R, MATCH_A, Effect::DENY, 2),
+        ]);
+
+        let actual = policy.apply(&"r".into(), &"a".into());
+
+        // priority only applies within the prioritized policy, the aggregate
+        // still denies if any of its constituents does
+        assert_eq!(actual.resolve(&1), Ok(ALLOW));
+        assert_eq!(actual.resolve(&2), Ok(DENY));
+
+        let policy = Policy::Prioritized(vec![
+            (
+                1,
+                Policy::Aggregate(vec![
+                    Policy::Conditional(MATCH_R, MATCH_A, Effect::ALLOW, 1),
+                    Policy::Conditional(MATCH_R, MATCH_A, Effect::DENY, 2),
+                ]),
+            ),
+            (0, Policy::Unconditional(MATCH_R, MATCH_A, Effect::ALLOW)),
+        ]);
+
+        let actual = policy.apply(&"r".into(), &"a".into());
+
+        // a silent aggregate defers to lower priorities
+        assert_eq!(actual.resolve(&1), Ok(ALLOW));
+        assert_eq!(actual.resolve(&2), Ok(DENY));
+        assert_eq!(actual.resolve(&3), Ok(ALLOW));
+    }
+
+    #[test]
+    fn test_obligated() {
+        use crate::obligation::*;
+        let audit = Duties::new().with_obligation(Duty::new("audit").with("stream", "security"));
+        let message = Duties::new().with_advice(Duty::new("show").with("text", "suspended"));
+        let policy = Policy::Aggregate(vec![
+            Policy::Obligated(
+                audit.clone(),
+                Box::new(Policy::Unconditional(MATCH_R, MATCH_A, Effect::ALLOW)),
+            ),
+            Policy::Obligated(
+                message.clone(),
+                Box::new(Policy::Conditional(MATCH_R, MATCH_A, Effect::DENY, 1)),
+            ),
+            Policy::Obligated(
+                message.clone(),
+                Box::new(Policy::Unconditional(MISS_R, MATCH_A, Effect::DENY)),
+            ),
+        ]);
+
+        let actual = policy.apply(&"r".into(), &"a".into());
+
+        assert_eq!(
+            actual,
+            DependentEffect::Aggregate(vec![
+                DependentEffect::Obligated(
+                    audit.clone(),
+                    Box::new(DependentEffect::Fixed(Effect::ALLOW))
+                ),
+                DependentEffect::Obligated(
+                    message.clone(),
+                    Box::new(DependentEffect::Atomic(Effect::DENY, 1))
+                ),
+                DependentEffect::Silent,
+            ])
+        );
+        assert_eq!(
+            actual.resolve_with_duties(&2),
+            Ok(Decision {
+                effect: ALLOW,
+                duties: audit
+            })
+        );
+        assert_eq!(
+            actual.resolve_with_duties(&1),
+            Ok(Decision {
+                effect: DENY,
+                duties: message
+            })
+        );
+    }
+
+    #[test]
+    fn test_annotated() {
+        use crate::metadata::*;
+        let policy = Policy::Aggregate(vec![
+            Policy::Annotated(
+                Metadata::new()
+                    .with_id("readers")
+                    .with_description("anyone can read")
+                    .with_tag("baseline")
+                    .with_owner("platform"),
+                Box::new(Policy::Unconditional(MATCH_R, MATCH_A, Effect::ALLOW)),
+            ),
+            Policy::Annotated(
+                Metadata::new().with_id("suspended"),
+                Box::new(Policy::Conditional(MATCH_R, MATCH_A, Effect::DENY, 1)),
+            ),
+            Policy::Annotated(
+                Metadata::new().with_description("no id"),
+                Box::new(Policy::Conditional(MATCH_R, MATCH_A, Effect::DENY, 2)),
+            ),
+            Policy::Annotated(
+                Metadata::new().with_id("unmatched"),
+                Box::new(Policy::Unconditional(MISS_R, MATCH_A, Effect::DENY)),
+            ),
+        ]);
+
+        assert_eq!(
+            policy.find("readers").and_then(Policy::metadata),
+            Some(
+                &Metadata::new()
+                    .with_id("readers")
+                    .with_description("anyone can read")
+                    .with_tag("baseline")
+                    .with_owner("platform")
+            )
+        );
+        assert!(policy.find("missing").is_none());
+
+        let actual = policy.apply(&"r".into(), &"a".into());
+
+        assert_eq!(
+            actual,
+            DependentEffect::Aggregate(vec![
+                DependentEffect::Identified(
+                    "readers".to_string(),
+                    Box::new(DependentEffect::Fixed(Effect::ALLOW))
+                ),
+                DependentEffect::Identified(
+                    "suspended".to_string(),
+                    Box::new(DependentEffect::Atomic(Effect::DENY, 1))
+                ),
+                DependentEffect::Atomic(Effect::DENY, 2),
+                DependentEffect::Silent,
+            ])
+        );
+        assert_eq!(
+            actual.resolve_attributed(&1),
+            Ok(Attribution {
+                effect: DENY,
+                statements: vec!["suspended".to_string()]
+            })
+        );
+        assert_eq!(
+            actual.resolve_attributed(&3),
+            Ok(Attribution {
+                effect: ALLOW,
+                statements: vec!["readers".to_string()]
+            })
+        );
+        // statements without an id are not reported
+        assert_eq!(actual.resolve_attributed(&2), Ok(DENY.into()));
+    }
+
     #[test]
     fn test_disjoint() {
         let policies = vec![
@@ -211,4 +716,115 @@ mod tests {
         );
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_apply_ref() {
+        use crate::metadata::*;
+        use crate::obligation::*;
+        let duties = Duties::new().with_obligation(Duty::new("audit"));
+        let policy = Policy::Aggregate(vec![
+            Policy::Conditional(MATCH_R, MATCH_A, Effect::ALLOW, 1),
+            Policy::Conditional(MISS_R, MATCH_A, Effect::DENY, 2),
+            Policy::Combined(
+                Algorithm::FirstApplicable,
+                vec![Policy::Unconditional(MATCH_R, MATCH_A, Effect::DENY)],
+            ),
+            Policy::Prioritized(vec![(
+                1,
+                Policy::Conditional(MATCH_R, MATCH_A, Effect::ALLOW, 3),
+            )]),
+            Policy::Obligated(
+                duties.clone(),
+                Box::new(Policy::Annotated(
+                    Metadata::new().with_id("s1"),
+                    Box::new(Policy::Conditional(MATCH_R, MATCH_A, Effect::DENY, 4)),
+                )),
+            ),
+            Policy::Annotated(
+                Metadata::new(),
+                Box::new(Policy::Conditional(MATCH_R, MATCH_A, Effect::ALLOW, 5)),
+            ),
+        ]);
+
+        let actual = policy.apply_ref(&"r".into(), &"a".into());
+
+        use DependentEffect::*;
+        assert_eq!(
+            actual,
+            Aggregate(vec![
+                Atomic(Effect::ALLOW, &1),
+                Silent,
+                Combined(Algorithm::FirstApplicable, vec![Fixed(Effect::DENY)]),
+                Prioritized(vec![(1, Atomic(Effect::ALLOW, &3))]),
+                Obligated(
+                    duties,
+                    Box::new(Identified(
+                        "s1".to_string(),
+                        Box::new(Atomic(Effect::DENY, &4))
+                    ))
+                ),
+                Atomic(Effect::ALLOW, &5),
+            ])
+        );
+
+        // the policy is still available
+        let owned = policy.clone().apply(&"r".into(), &"a".into());
+        for value in 0..6 {
+            assert_eq!(actual.resolve(&Borrowed(&value)), owned.resolve(&value));
+        }
+    }
+
+    #[test]
+    fn test_disjoint_borrowed() {
+        let policies = vec![
+            Policy::Conditional(MATCH_R, MATCH_A, Effect::ALLOW, 18),
+            Policy::Conditional(MISS_R, MATCH_A, Effect::DENY, 19),
+            Policy::Unconditional(MATCH_R, MATCH_A, Effect::ALLOW),
+        ];
+        let r = "r".into();
+        let a = "a".into();
+
+        let actual = apply_disjoint(&policies, &r, &a);
+
+        assert_eq!(
+            actual,
+            DependentEffect::Disjoint(vec![
+                DependentEffect::Atomic(Effect::ALLOW, &18),
+                DependentEffect::Silent,
+                DependentEffect::Fixed(Effect::ALLOW),
+            ])
+        );
+        assert_eq!(
+            actual.resolve(&Borrowed(&18)),
+            apply_disjoint(policies, &r, &a).resolve(&18)
+        );
+    }
+
+    #[test]
+    fn test_apply_ref_concurrently() {
+        let policies = vec![
+            Policy::Conditional(StrResource("r1"), MATCH_A, Effect::ALLOW, 1),
+            Policy::Conditional(StrResource("r2"), MATCH_A, Effect::ALLOW, 2),
+        ];
+
+        let results: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (1..=2u32)
+                .map(|value| {
+                    let policies = &policies;
+                    scope.spawn(move || {
+                        let resource = format!("r{}", value);
+                        let effect = apply_disjoint(
+                            policies.iter().take(value as usize),
+                            &resource.as_str().into(),
+                            &"a".into(),
+                        );
+                        effect.resolve(&Borrowed(&value))
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        assert_eq!(results, vec![Ok(ALLOW), Ok(SILENT)]);
+    }
 }
@@ -3,7 +3,9 @@
 //! Templates allow parameterized policies based on modifying resources. They allow for things
 //! like symbolic roles that can be scoped to resources.
 
-use super::effect::Effect;
+use super::effect::{Algorithm, Effect, Priority};
+use super::metadata::Metadata;
+use super::obligation::Duties;
 use super::policy::*;
 
 /// General parameterization trait
@@ -26,6 +28,14 @@ pub enum PolicyTemplate<RMatchTpl, AMatch, CExp> {
     Conditional(RMatchTpl, AMatch, Effect, CExp),
     /// generates a `Policy::Aggregate` by applying the parameter to all of its consituents
     Aggregate(Vec<PolicyTemplate<RMatchTpl, AMatch, CExp>>),
+    /// generates a `Policy::Combined` by applying the parameter to all of its consituents
+    Combined(Algorithm, Vec<PolicyTemplate<RMatchTpl, AMatch, CExp>>),
+    /// generates a `Policy::Prioritized` by applying the parameter to all of its consituents
+    Prioritized(Vec<(Priority, PolicyTemplate<RMatchTpl, AMatch, CExp>)>),
+    /// generates a `Policy::Obligated` by applying the parameter to the wrapped template
+    Obligated(Duties, Box<PolicyTemplate<RMatchTpl, AMatch, CExp>>),
+    /// generates a `Policy::Annotated` by applying the parameter to the wrapped template
+    Annotated(Metadata, Box<PolicyTemplate<RMatchTpl, AMatch, CExp>>),
 }
 
 impl<Param, RMatchTpl, RMatch, AMatch, CExp> Template<Policy<RMatch, AMatch, CExp>>
@@ -42,6 +52,16 @@ where
                 let policy = elems.into_iter().map(|e| e.apply(p)).collect();
                 Policy::Aggregate(policy)
             }
+            Combined(algorithm, elems) => {
+                let policy = elems.into_iter().map(|e| e.apply(p)).collect();
+                Policy::Combined(algorithm, policy)
+            }
+            Prioritized(elems) => {
+                let policy = elems.into_iter().map(|(pr, e)| (pr, e.apply(p))).collect();
+                Policy::Prioritized(policy)
+            }
+            Obligated(duties, elem) => Policy::Obligated(duties, Box::new(elem.apply(p))),
+            Annotated(metadata, elem) => Policy::Annotated(metadata, Box::new(elem.apply(p))),
             Unconditional(rmtpl, am, eff) => Policy::Unconditional(rmtpl.apply(p), am, eff),
             Conditional(rmtpl, am, eff, cond) => Policy::Conditional(rmtpl.apply(p), am, eff, cond),
         }
@@ -110,6 +130,74 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_combined() {
+        use PolicyTemplate::*;
+        let elems = vec![
+            Unconditional(RMatchTpl, AMatch("a1"), Effect::ALLOW),
+            Conditional(RMatchTpl, AMatch("a2"), Effect::DENY, Cond("c1")),
+        ];
+        let template = Combined(Algorithm::FirstApplicable, elems.clone());
+
+        let actual = template.apply(&"param");
+
+        let expected = elems.into_iter().map(|e| e.apply(&"param")).collect();
+        let expected = Policy::Combined(Algorithm::FirstApplicable, expected);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_prioritized() {
+        use PolicyTemplate::*;
+        let elems = vec![
+            (1, Unconditional(RMatchTpl, AMatch("a1"), Effect::DENY)),
+            (
+                2,
+                Conditional(RMatchTpl, AMatch("a2"), Effect::ALLOW, Cond("c1")),
+            ),
+        ];
+        let template = Prioritized(elems.clone());
+
+        let actual = template.apply(&"param");
+
+        let expected = elems
+            .into_iter()
+            .map(|(pr, e)| (pr, e.apply(&"param")))
+            .collect();
+        let expected = Policy::Prioritized(expected);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_obligated() {
+        use crate::obligation::*;
+        use PolicyTemplate::*;
+        let duties = Duties::new().with_obligation(Duty::new("audit"));
+        let elem =
+            PolicyTemplate::<_, _, Cond>::Unconditional(RMatchTpl, AMatch("a1"), Effect::ALLOW);
+        let template = Obligated(duties.clone(), Box::new(elem.clone()));
+
+        let actual = template.apply(&"param");
+
+        let expected = Policy::Obligated(duties, Box::new(elem.apply(&"param")));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_annotated() {
+        use crate::metadata::*;
+        use PolicyTemplate::*;
+        let metadata = Metadata::new().with_id("s1").with_tag("scoped");
+        let elem =
+            PolicyTemplate::<_, _, Cond>::Unconditional(RMatchTpl, AMatch("a1"), Effect::ALLOW);
+        let template = Annotated(metadata.clone(), Box::new(elem.clone()));
+
+        let actual = template.apply(&"param");
+
+        let expected = Policy::Annotated(metadata, Box::new(elem.apply(&"param")));
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn test_unconditional_allow() {
         let rmatch_tpl = RMatchTpl;
@@ -1,14 +1,25 @@
 use super::matcher::*;
-use super::path::*;
 
 /// Trait for matching resources. When evaluating a policy, this is used to determine if
-/// the policy applies with respect to a concrete resource.
+/// the policy applies with respect to a concrete resource. Every `Matcher` is a
+/// resource matcher for its target type.
 pub trait ResourceMatch {
     /// The type of resource that can be matched.
     type Resource;
 
     /// Determine if a concrete resource matches
-    fn test(&self, resource: &Self::Resource) -> bool;
+    fn test_resource(&self, resource: &Self::Resource) -> bool;
+}
+
+impl<M> ResourceMatch for M
+where
+    M: Matcher,
+{
+    type Resource = M::Target;
+
+    fn test_resource(&self, resource: &Self::Resource) -> bool {
+        self.test(resource)
+    }
 }
 
 /// Trivial resource represented by a string. Can also be used as a matcher.
@@ -34,10 +45,48 @@ impl<'a> Matcher for StrResource<'a> {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-struct ResourcePath(Vec<PathElem>);
+/// Trivial resource represented by an owned string. Can also be used as a matcher.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord)]
+pub struct StringResource(pub String);
+
+impl StringResource {
+    pub fn new<S: Into<String>>(v: S) -> Self {
+        StringResource(v.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for StringResource {
+    fn from(v: &str) -> Self {
+        StringResource(v.into())
+    }
+}
+
+impl From<String> for StringResource {
+    fn from(v: String) -> Self {
+        StringResource(v)
+    }
+}
+
+impl<'a> From<StrResource<'a>> for StringResource {
+    fn from(v: StrResource<'a>) -> Self {
+        StringResource(v.0.into())
+    }
+}
+
+impl Matcher for StringResource {
+    type Target = Self;
+
+    fn test(&self, target: &Self::Target) -> bool {
+        self.0 == target.0
+    }
+}
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
 
     use super::*;
@@ -49,4 +98,21 @@ mod tests {
         assert_eq!(StrResource("abc").test(&resource), true);
         assert_eq!(StrResource("xyz").test(&resource), false);
     }
+
+    #[test]
+    fn test_string_resource_matcher() {
+        let resource = StringResource::new("abc");
+
+        assert!(StringResource::from("abc").test(&resource));
+        assert!(!StringResource::from("xyz".to_string()).test(&resource));
+        assert_eq!(StringResource::from(StrResource("abc")), resource);
+    }
+
+    #[test]
+    fn test_matcher_is_resource_match() {
+        let resource = StrResource("abc");
+
+        assert!(StrResource("abc").test_resource(&resource));
+        assert!(!StrResource("xyz").test_resource(&resource));
+    }
 }
@@ -0,0 +1,468 @@
+//! Algebraic simplification of dependent effects.
+//!
+//! Non-strict and strict combination are both associative, commutative and
+//! idempotent, silence is the identity of non-strict combination and `ALLOW`
+//! the identity of a non-empty strict combination. `DependentEffect::simplify`
+//! uses these laws to normalize the trees produced by applying policies.
+//! Combinations using other algorithms and prioritized combinations are
+//! simplified within, pruned and folded once decided.
+
+use super::dependent_effect::*;
+use super::effect::*;
+use super::partial::{annotated, decided_prefix, denied_aggregate, fixed, prune_prioritized};
+
+impl<CExp> DependentEffect<CExp>
+where
+    CExp: PartialEq,
+{
+    /// Normalize a dependent effect without changing how it resolves.
+    ///
+    /// * nested `Aggregate`s are flattened into their parent, as are nested `Disjoint`s
+    /// * silence is dropped from an `Aggregate` and a `Fixed(DENY)` replaces it
+    /// * silence replaces a `Disjoint` and `Fixed(ALLOW)` is dropped from it
+    ///   unless nothing else remains
+    /// * duplicate constituents are removed, keeping the first
+    /// * an empty combination becomes `Silent` and a combination of a single
+    ///   constituent becomes the constituent
+    /// * a `Combined` using the non-strict or strict algorithm becomes an
+    ///   `Aggregate` or `Disjoint`; other `Combined`s keep their constituents
+    ///   and become their result once the decided constituents leading them
+    ///   decide it
+    /// * silence and constituents below the priority of a fixed effect are
+    ///   dropped from a `Prioritized`, which becomes its result once its
+    ///   highest-priority constituents decide it
+    /// * empty duties are dropped and silence replaces an `Obligated` or
+    ///   `Identified` silence
+    ///
+    /// Constituents carrying duties or statement ids are kept while they can
+    /// contribute to the effect, so the result reports the same duties and
+    /// statements.
+    ///
+    /// The result resolves to the same effect as the original in every
+    /// environment whose condition tests are deterministic and do not fail.
+    /// Since fewer conditions may be tested, an environment that fails can
+    /// produce an effect where the original produced an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use authorization_core::dependent_effect::*;
+    /// use authorization_core::effect::*;
+    ///
+    /// use DependentEffect::*;
+    /// let effect = Aggregate(vec![
+    ///     Silent,
+    ///     Aggregate(vec![Atomic(Effect::ALLOW, "a"), Silent]),
+    ///     Aggregate(vec![Atomic(Effect::ALLOW, "a"), Atomic(Effect::DENY, "b")]),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     effect.simplify(),
+    ///     Aggregate(vec![Atomic(Effect::ALLOW, "a"), Atomic(Effect::DENY, "b")])
+    /// );
+    /// ```
+    pub fn simplify(self) -> Self {
+        use DependentEffect::*;
+        match self {
+            Aggregate(effs) => {
+                let mut simplified = Vec::with_capacity(effs.len());
+                let mut denied = false;
+                for eff in effs {
+                    match eff.simplify() {
+                        Fixed(Effect::DENY) => denied = true,
+                        Silent => {}
+                        Aggregate(inner) => inner.into_iter().for_each(|e| match e {
+                            Fixed(Effect::DENY) => denied = true,
+                            e => push_unique(&mut simplified, e),
+                        }),
+                        e => push_unique(&mut simplified, e),
+                    }
+                }
+                if denied {
+                    return denied_aggregate(simplified);
+                }
+                match simplified.len() {
+                    0 => Silent,
+                    1 => simplified.pop().unwrap(),
+                    _ => Aggregate(simplified),
+                }
+            }
+            Disjoint(effs) => {
+                let mut simplified = Vec::with_capacity(effs.len());
+                let mut allowed = false;
+                for eff in effs {
+                    match eff.simplify() {
+                        Silent => return Silent,
+                        Fixed(Effect::ALLOW) => allowed = true,
+                        Disjoint(inner) => inner
+                            .into_iter()
+                            .for_each(|e| push_unique(&mut simplified, e)),
+                        e => push_unique(&mut simplified, e),
+                    }
+                }
+                match simplified.len() {
+                    0 if allowed => Fixed(Effect::ALLOW),
+                    0 => Silent,
+                    1 => simplified.pop().unwrap(),
+                    _ => Disjoint(simplified),
+                }
+            }
+            Combined(Algorithm::NonStrict, effs) => Aggregate(effs).simplify(),
+            Combined(Algorithm::Strict, effs) => Disjoint(effs).simplify(),
+            Combined(algorithm, effs) => {
+                let simplified: Vec<_> = effs.into_iter().map(Self::simplify).collect();
+                match decided_prefix(&simplified, &algorithm) {
+                    Some(decided) if !simplified.iter().any(annotated) => fixed(decided),
+                    _ => Combined(algorithm, simplified),
+                }
+            }
+            Prioritized(effs) => {
+                prune_prioritized(effs.into_iter().map(|(p, e)| (p, e.simplify())).collect())
+            }
+            Obligated(duties, eff) => match eff.simplify() {
+                Silent => Silent,
+                eff if duties.is_empty() => eff,
+                eff => Obligated(duties, Box::new(eff)),
+            },
+            Identified(id, eff) => match eff.simplify() {
+                Silent => Silent,
+                eff => Identified(id, Box::new(eff)),
+            },
+            leaf => leaf,
+        }
+    }
+}
+
+/// Private helper. Add an effect unless an equal effect is present.
+fn push_unique<CExp: PartialEq>(effs: &mut Vec<DependentEffect<CExp>>, eff: DependentEffect<CExp>) {
+    if !effs.contains(&eff) {
+        effs.push(eff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::obligation::*;
+    use crate::partial::tests::Bits;
+
+    const CONDITIONS: u32 = 4;
+
+    /// Deterministic xorshift generator of arbitrary dependent effects, with
+    /// duties and statement ids attached if the flag is set.
+    struct Generator(u64, bool);
+
+    impl Generator {
+        fn next(&mut self, bound: u32) -> u32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % u64::from(bound)) as u32
+        }
+
+        fn effect(&mut self) -> Effect {
+            if self.next(2) == 0 {
+                Effect::ALLOW
+            } else {
+                Effect::DENY
+            }
+        }
+
+        fn dependent_effect(&mut self, depth: u32) -> DependentEffect<u32> {
+            use DependentEffect::*;
+            let kinds = match (depth, self.1) {
+                (0, _) => 3,
+                (_, false) => 7,
+                (_, true) => 9,
+            };
+            match self.next(kinds) {
+                0 => Silent,
+                1 => Fixed(self.effect()),
+                2 => Atomic(self.effect(), self.next(CONDITIONS)),
+                7 => {
+                    let duties = Duties::new().with_obligation(Duty::new(self.next(3).to_string()));
+                    Obligated(duties, Box::new(self.dependent_effect(depth - 1)))
+                }
+                8 => Identified(
+                    self.next(3).to_string(),
+                    Box::new(self.dependent_effect(depth - 1)),
+                ),
+                kind => {
+                    let effs: Vec<_> = (0..self.next(4))
+                        .map(|_| self.dependent_effect(depth - 1))
+                        .collect();
+                    match kind {
+                        3 => Aggregate(effs),
+                        4 => Disjoint(effs),
+                        5 => Combined(Algorithm::ALL[self.next(7) as usize].clone(), effs),
+                        _ => Prioritized(effs.into_iter().map(|e| (self.next(3), e)).collect()),
+                    }
+                }
+            }
+        }
+    }
+
+    fn samples() -> Vec<DependentEffect<u32>> {
+        let mut gen = Generator(0x2545_f491_4f6c_dd1d, false);
+        (0..2000).map(|_| gen.dependent_effect(4)).collect()
+    }
+
+    fn annotated_samples() -> Vec<DependentEffect<u32>> {
+        let mut gen = Generator(0x9e37_79b9_7f4a_7c15, true);
+        (0..2000).map(|_| gen.dependent_effect(4)).collect()
+    }
+
+    /// Private helper. Check the structural guarantees of a simplified effect.
+    fn check_normal(eff: &DependentEffect<u32>) {
+        use DependentEffect::*;
+        match eff {
+            Aggregate(effs) => {
+                assert!(effs.len() > 1, "{:?}", eff);
+                for (i, e) in effs.iter().enumerate() {
+                    assert!(!matches!(e, Silent | Fixed(Effect::DENY) | Aggregate(_)));
+                    assert!(!effs[..i].contains(e));
+                    check_normal(e);
+                }
+            }
+            Disjoint(effs) => {
+                assert!(effs.len() > 1, "{:?}", eff);
+                for (i, e) in effs.iter().enumerate() {
+                    assert!(!matches!(e, Silent | Fixed(Effect::ALLOW) | Disjoint(_)));
+                    assert!(!effs[..i].contains(e));
+                    check_normal(e);
+                }
+            }
+            Combined(algorithm, effs) => {
+                assert!(!matches!(
+                    algorithm,
+                    Algorithm::NonStrict | Algorithm::Strict
+                ));
+                assert_eq!(decided_prefix(effs, algorithm), None, "{:?}", eff);
+                effs.iter().for_each(check_normal);
+            }
+            Prioritized(effs) => {
+                assert!(effs.len() > 1, "{:?}", eff);
+                let top = effs.iter().map(|(p, _)| *p).max();
+                for (p, e) in effs {
+                    assert!(!matches!(e, Silent));
+                    assert!(!(Some(*p) == top && matches!(e, Fixed(Effect::DENY))));
+                    assert!(!effs.iter().any(|(q, f)| q > p && matches!(f, Fixed(_))));
+                    check_normal(e);
+                }
+                assert!(!effs.iter().all(|(_, e)| matches!(e, Fixed(_))));
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_simplify_resolves_identically() {
+        for eff in samples() {
+            let simplified = eff.clone().simplify();
+
+            for bits in 0..(1 << CONDITIONS) {
+                assert_eq!(
+                    simplified.resolve(&Bits(bits)),
+                    eff.resolve(&Bits(bits)),
+                    "{:?} simplified to {:?}",
+                    eff,
+                    simplified
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_simplify_normalizes() {
+        for eff in samples() {
+            check_normal(&eff.simplify());
+        }
+    }
+
+    #[test]
+    fn test_simplify_is_idempotent() {
+        for eff in samples() {
+            let simplified = eff.simplify();
+
+            assert_eq!(simplified.clone().simplify(), simplified);
+        }
+    }
+
+    #[test]
+    fn test_simplify_keeps_contributing_annotations() {
+        for eff in annotated_samples() {
+            let simplified = eff.clone().simplify();
+
+            for bits in 0..(1 << CONDITIONS) {
+                assert_eq!(
+                    simplified.resolve_with_duties(&Bits(bits)),
+                    eff.resolve_with_duties(&Bits(bits)),
+                    "{:?} simplified to {:?}",
+                    eff,
+                    simplified
+                );
+                assert_eq!(
+                    simplified.resolve_attributed(&Bits(bits)),
+                    eff.resolve_attributed(&Bits(bits)),
+                    "{:?} simplified to {:?}",
+                    eff,
+                    simplified
+                );
+            }
+            assert_eq!(simplified.clone().simplify(), simplified);
+        }
+    }
+
+    #[test]
+    fn test_simplify_obligated() {
+        use DependentEffect::*;
+        let audit = Duties::new().with_obligation(Duty::new("audit"));
+
+        // denial keeps the constituents that could contribute duties
+        let eff = Aggregate(vec![
+            Atomic(Effect::ALLOW, 1),
+            Obligated(audit.clone(), Box::new(Atomic(Effect::DENY, 2))),
+            Fixed(Effect::DENY),
+        ]);
+        assert_eq!(
+            eff.simplify(),
+            Aggregate(vec![
+                Fixed(Effect::DENY),
+                Obligated(audit.clone(), Box::new(Atomic(Effect::DENY, 2)))
+            ])
+        );
+
+        let eff = Obligated::<u32>(audit, Box::new(Aggregate(vec![Silent])));
+        assert_eq!(eff.simplify(), Silent);
+
+        let eff = Obligated(Duties::new(), Box::new(Atomic(Effect::ALLOW, 1)));
+        assert_eq!(eff.simplify(), Atomic(Effect::ALLOW, 1));
+    }
+
+    #[test]
+    fn test_simplify_aggregate() {
+        use DependentEffect::*;
+
+        let eff = Aggregate(vec![
+            Atomic(Effect::ALLOW, 1),
+            Aggregate(vec![Silent, Atomic(Effect::ALLOW, 2)]),
+            Fixed(Effect::DENY),
+        ]);
+        assert_eq!(eff.simplify(), Fixed(Effect::DENY));
+
+        let eff = Aggregate(vec![
+            Atomic(Effect::ALLOW, 1),
+            Aggregate(vec![
+                Silent,
+                Atomic(Effect::ALLOW, 2),
+                Atomic(Effect::ALLOW, 1),
+            ]),
+        ]);
+        assert_eq!(
+            eff.simplify(),
+            Aggregate(vec![Atomic(Effect::ALLOW, 1), Atomic(Effect::ALLOW, 2)])
+        );
+
+        let eff = Aggregate(vec![Silent, Aggregate(vec![Atomic(Effect::DENY, 1)])]);
+        assert_eq!(eff.simplify(), Atomic(Effect::DENY, 1));
+
+        assert_eq!(Aggregate::<u32>(vec![Silent, Silent]).simplify(), Silent);
+    }
+
+    #[test]
+    fn test_simplify_disjoint() {
+        use DependentEffect::*;
+
+        let eff = Disjoint(vec![Atomic(Effect::ALLOW, 1), Aggregate(vec![])]);
+        assert_eq!(eff.simplify(), Silent);
+
+        let eff = Disjoint(vec![
+            Fixed(Effect::ALLOW),
+            Disjoint(vec![Atomic(Effect::ALLOW, 1), Fixed(Effect::DENY)]),
+            Atomic(Effect::ALLOW, 1),
+        ]);
+        assert_eq!(
+            eff.simplify(),
+            Disjoint(vec![Atomic(Effect::ALLOW, 1), Fixed(Effect::DENY)])
+        );
+
+        let eff = Disjoint::<u32>(vec![Fixed(Effect::ALLOW), Fixed(Effect::ALLOW)]);
+        assert_eq!(eff.simplify(), Fixed(Effect::ALLOW));
+
+        // an empty disjoint is silent rather than allowed
+        assert_eq!(Disjoint::<u32>(vec![]).simplify(), Silent);
+
+        // combinations of the other kind are not flattened
+        let eff = Disjoint(vec![
+            Aggregate(vec![Atomic(Effect::ALLOW, 1), Atomic(Effect::ALLOW, 2)]),
+            Atomic(Effect::ALLOW, 3),
+        ]);
+        assert_eq!(eff.clone().simplify(), eff);
+    }
+
+    #[test]
+    fn test_simplify_combined() {
+        use DependentEffect::*;
+
+        let eff = Combined(Algorithm::NonStrict, vec![Atomic(Effect::ALLOW, 1), Silent]);
+        assert_eq!(eff.simplify(), Atomic(Effect::ALLOW, 1));
+
+        let eff = Combined(
+            Algorithm::FirstApplicable,
+            vec![
+                Aggregate(vec![Silent]),
+                Fixed(Effect::ALLOW),
+                Atomic(Effect::DENY, 1),
+            ],
+        );
+        assert_eq!(eff.simplify(), Fixed(Effect::ALLOW));
+
+        // constituents are kept in place for the algorithm
+        let eff = Combined(
+            Algorithm::FirstApplicable,
+            vec![
+                Atomic(Effect::DENY, 1),
+                Aggregate(vec![Silent]),
+                Atomic(Effect::DENY, 1),
+            ],
+        );
+        assert_eq!(
+            eff.simplify(),
+            Combined(
+                Algorithm::FirstApplicable,
+                vec![Atomic(Effect::DENY, 1), Silent, Atomic(Effect::DENY, 1)]
+            )
+        );
+    }
+
+    #[test]
+    fn test_simplify_prioritized() {
+        use DependentEffect::*;
+
+        let eff = Prioritized(vec![
+            (1, Atomic(Effect::DENY, 1)),
+            (2, Aggregate(vec![Fixed(Effect::ALLOW), Silent])),
+            (3, Disjoint(vec![Atomic(Effect::DENY, 2), Silent])),
+            (2, Atomic(Effect::DENY, 3)),
+        ]);
+        assert_eq!(
+            eff.simplify(),
+            Prioritized(vec![
+                (2, Fixed(Effect::ALLOW)),
+                (2, Atomic(Effect::DENY, 3))
+            ])
+        );
+
+        let eff = Prioritized(vec![
+            (2, Atomic(Effect::ALLOW, 1)),
+            (2, Fixed(Effect::DENY)),
+        ]);
+        assert_eq!(eff.simplify(), Fixed(Effect::DENY));
+
+        let eff = Prioritized(vec![(2, Silent), (1, Atomic(Effect::ALLOW, 1))]);
+        assert_eq!(eff.simplify(), Atomic(Effect::ALLOW, 1));
+
+        assert_eq!(Prioritized::<u32>(vec![]).simplify(), Silent);
+    }
+}
@@ -0,0 +1,514 @@
+//! Time based conditions.
+//!
+//! `TimeCondition`s restrict policies to instants or recurring windows, e.g.
+//! business hours or until an expiry date. They are evaluated by a
+//! `ClockEnvironment` against a pluggable `Clock` so tests can fix the time.
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::environment::*;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// An instant, in whole seconds since the Unix epoch (1970-01-01T00:00:00Z).
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct Timestamp(pub i64);
+
+impl From<SystemTime> for Timestamp {
+    fn from(t: SystemTime) -> Self {
+        match t.duration_since(UNIX_EPOCH) {
+            Ok(d) => Timestamp(d.as_secs() as i64),
+            Err(e) => Timestamp(-(e.duration().as_secs() as i64)),
+        }
+    }
+}
+
+/// Fixed offset from UTC of less than a day, e.g. `UtcOffset::hours(-5)` for US
+/// Eastern standard time.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct UtcOffset(i32);
+
+impl UtcOffset {
+    pub const UTC: UtcOffset = UtcOffset(0);
+
+    /// Offset of whole hours. Returns `None` unless the offset is less than a
+    /// day in either direction.
+    pub fn hours(hours: i32) -> Option<Self> {
+        if hours.unsigned_abs() < 24 {
+            Some(UtcOffset(hours * 3600))
+        } else {
+            None
+        }
+    }
+
+    /// Offset in minutes, e.g. `UtcOffset::minutes(-(3 * 60 + 30))` for
+    /// -03:30. Returns `None` unless the offset is less than a day in either
+    /// direction.
+    pub fn minutes(minutes: i32) -> Option<Self> {
+        if minutes.unsigned_abs() < 24 * 60 {
+            Some(UtcOffset(minutes * 60))
+        } else {
+            None
+        }
+    }
+
+    /// The offset in seconds.
+    pub fn seconds(self) -> i32 {
+        self.0
+    }
+
+    /// Private helper. Seconds since the epoch in local time. Saturates at the
+    /// limits of `i64`.
+    fn local(self, t: Timestamp) -> i64 {
+        t.0.saturating_add(i64::from(self.0))
+    }
+}
+
+/// Day of the week.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Weekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl Weekday {
+    const ALL: [Weekday; 7] = [
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+        Weekday::Sun,
+    ];
+
+    /// Day of the week of an instant at a UTC offset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use authorization_core::time::*;
+    ///
+    /// assert_eq!(Weekday::of(Timestamp(0), UtcOffset::UTC), Weekday::Thu);
+    /// assert_eq!(Weekday::of(Timestamp(0), UtcOffset::hours(-1).unwrap()), Weekday::Wed);
+    /// ```
+    pub fn of(t: Timestamp, offset: UtcOffset) -> Self {
+        // the epoch was a Thursday
+        let days = offset.local(t).div_euclid(SECONDS_PER_DAY);
+        Self::ALL[(days + 3).rem_euclid(7) as usize]
+    }
+}
+
+/// Time of day in seconds since midnight.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct TimeOfDay(u32);
+
+impl TimeOfDay {
+    /// Construct a time of day. Returns `None` if a component is out of range.
+    pub fn hms(hours: u32, minutes: u32, seconds: u32) -> Option<Self> {
+        if hours < 24 && minutes < 60 && seconds < 60 {
+            Some(TimeOfDay(hours * 3600 + minutes * 60 + seconds))
+        } else {
+            None
+        }
+    }
+
+    /// Time of day of an instant at a UTC offset.
+    pub fn of(t: Timestamp, offset: UtcOffset) -> Self {
+        TimeOfDay(offset.local(t).rem_euclid(SECONDS_PER_DAY) as u32)
+    }
+
+    /// Seconds since midnight.
+    pub fn seconds(self) -> u32 {
+        self.0
+    }
+}
+
+/// Condition on the current time.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TimeCondition {
+    /// Holds strictly before an instant.
+    Before(Timestamp),
+    /// Holds at and after an instant.
+    After(Timestamp),
+    /// Holds on the listed days of the week at a UTC offset.
+    DaysOfWeek(UtcOffset, Vec<Weekday>),
+    /// Holds from `start` (inclusive) to `end` (exclusive) each day at a UTC
+    /// offset. If `end` is before `start` the window spans midnight.
+    TimeOfDay {
+        offset: UtcOffset,
+        start: TimeOfDay,
+        end: TimeOfDay,
+    },
+    /// Holds for `duration` seconds every `period` seconds, starting at `start`.
+    /// It never holds before `start` or if `period` is not positive. Any
+    /// timestamps are allowed.
+    Recurring {
+        start: Timestamp,
+        period: i64,
+        duration: i64,
+    },
+    /// Holds if every constituent holds.
+    All(Vec<TimeCondition>),
+    /// Holds if any constituent holds.
+    Any(Vec<TimeCondition>),
+    /// Holds if the constituent does not hold.
+    Not(Box<TimeCondition>),
+}
+
+impl TimeCondition {
+    /// Business hours: the given time window on weekdays, at a UTC offset.
+    pub fn weekdays_between(offset: UtcOffset, start: TimeOfDay, end: TimeOfDay) -> Self {
+        use Weekday::*;
+        TimeCondition::All(vec![
+            TimeCondition::DaysOfWeek(offset, vec![Mon, Tue, Wed, Thu, Fri]),
+            TimeCondition::TimeOfDay { offset, start, end },
+        ])
+    }
+
+    /// Determine if the condition holds at an instant.
+    pub fn holds_at(&self, now: Timestamp) -> bool {
+        use TimeCondition::*;
+        match self {
+            Before(t) => now < *t,
+            After(t) => now >= *t,
+            DaysOfWeek(offset, days) => days.contains(&Weekday::of(now, *offset)),
+            TimeOfDay { offset, start, end } => {
+                let t = self::TimeOfDay::of(now, *offset);
+                if start <= end {
+                    *start <= t && t < *end
+                } else {
+                    *start <= t || t < *end
+                }
+            }
+            Recurring {
+                start,
+                period,
+                duration,
+            } => {
+                // the elapsed time can exceed i64 for extreme timestamps
+                let elapsed = i128::from(now.0) - i128::from(start.0);
+                *period > 0
+                    && now >= *start
+                    && elapsed % i128::from(*period) < i128::from(*duration)
+            }
+            All(cs) => cs.iter().all(|c| c.holds_at(now)),
+            Any(cs) => cs.iter().any(|c| c.holds_at(now)),
+            Not(c) => !c.holds_at(now),
+        }
+    }
+}
+
+/// Source of the current time.
+pub trait Clock {
+    fn now(&self) -> Timestamp;
+}
+
+/// Clock reading the system time.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        SystemTime::now().into()
+    }
+}
+
+/// Clock that always reads the same instant.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FixedClock(pub Timestamp);
+
+impl Clock for FixedClock {
+    fn now(&self) -> Timestamp {
+        self.0
+    }
+}
+
+/// Clock that only moves when told to. It can be shared between threads.
+#[derive(Debug, Default)]
+pub struct ManualClock(AtomicI64);
+
+impl ManualClock {
+    pub fn new(now: Timestamp) -> Self {
+        ManualClock(AtomicI64::new(now.0))
+    }
+
+    /// Set the current time.
+    pub fn set(&self, now: Timestamp) {
+        self.0.store(now.0, Ordering::SeqCst);
+    }
+
+    /// Move the current time forward by a number of seconds.
+    pub fn advance(&self, seconds: i64) {
+        self.0.fetch_add(seconds, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Timestamp {
+        Timestamp(self.0.load(Ordering::SeqCst))
+    }
+}
+
+impl<C> Clock for &C
+where
+    C: Clock + ?Sized,
+{
+    fn now(&self) -> Timestamp {
+        (**self).now()
+    }
+}
+
+/// Environment evaluating `TimeCondition`s against a clock. The clock is read
+/// once per condition.
+///
+/// # Examples
+///
+/// ```
+/// use authorization_core::environment::*;
+/// use authorization_core::time::*;
+///
+/// let nine = TimeOfDay::hms(9, 0, 0).unwrap();
+/// let five = TimeOfDay::hms(17, 0, 0).unwrap();
+/// let business_hours = TimeCondition::weekdays_between(UtcOffset::UTC, nine, five);
+///
+/// // Friday 1970-01-02 at 10:00 UTC
+/// let clock = ManualClock::new(Timestamp(86_400 + 10 * 3600));
+/// let env = ClockEnvironment::new(&clock);
+/// assert_eq!(env.test_condition(&business_hours), Ok(true));
+///
+/// // Saturday
+/// clock.advance(86_400);
+/// assert_eq!(env.test_condition(&business_hours), Ok(false));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClockEnvironment<C>(C);
+
+impl<C> ClockEnvironment<C>
+where
+    C: Clock,
+{
+    pub fn new(clock: C) -> Self {
+        ClockEnvironment(clock)
+    }
+}
+
+impl<C> ReliableEnvironment for ClockEnvironment<C>
+where
+    C: Clock,
+{
+    type CExp = TimeCondition;
+
+    fn reliably_test_condition(&self, exp: &Self::CExp) -> bool {
+        exp.holds_at(self.0.now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dependent_effect::*;
+    use crate::effect::*;
+
+    /// Monday 2024-01-01T00:00:00Z
+    const MONDAY: i64 = 1_704_067_200;
+    const HOUR: i64 = 3600;
+
+    fn at(seconds: i64) -> Timestamp {
+        Timestamp(MONDAY + seconds)
+    }
+
+    fn tod(h: u32, m: u32) -> TimeOfDay {
+        TimeOfDay::hms(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_before_after() {
+        let expiry = Timestamp(1000);
+
+        assert!(TimeCondition::Before(expiry).holds_at(Timestamp(999)));
+        assert!(!TimeCondition::Before(expiry).holds_at(Timestamp(1000)));
+        assert!(TimeCondition::After(expiry).holds_at(Timestamp(1000)));
+        assert!(!TimeCondition::After(expiry).holds_at(Timestamp(999)));
+    }
+
+    #[test]
+    fn test_weekday() {
+        assert_eq!(Weekday::of(at(0), UtcOffset::UTC), Weekday::Mon);
+        assert_eq!(Weekday::of(at(6 * 24 * HOUR), UtcOffset::UTC), Weekday::Sun);
+        assert_eq!(Weekday::of(at(-1), UtcOffset::UTC), Weekday::Sun);
+        assert_eq!(
+            Weekday::of(at(-1), UtcOffset::hours(1).unwrap()),
+            Weekday::Mon
+        );
+        assert_eq!(Weekday::of(Timestamp(-1), UtcOffset::UTC), Weekday::Wed);
+    }
+
+    #[test]
+    fn test_days_of_week() {
+        let weekend = TimeCondition::DaysOfWeek(UtcOffset::UTC, vec![Weekday::Sat, Weekday::Sun]);
+
+        assert!(!weekend.holds_at(at(0)));
+        assert!(weekend.holds_at(at(5 * 24 * HOUR)));
+        assert!(weekend.holds_at(at(-1)));
+    }
+
+    #[test]
+    fn test_time_of_day() {
+        assert_eq!(
+            TimeOfDay::of(at(HOUR + 1), UtcOffset::UTC),
+            TimeOfDay::hms(1, 0, 1).unwrap()
+        );
+        assert_eq!(
+            TimeOfDay::of(at(HOUR), UtcOffset::hours(-2).unwrap()),
+            tod(23, 0)
+        );
+        assert_eq!(
+            TimeOfDay::of(at(0), UtcOffset::minutes(-(3 * 60 + 30)).unwrap()),
+            tod(20, 30)
+        );
+        assert_eq!(
+            TimeOfDay::of(at(0), UtcOffset::minutes(-30).unwrap()),
+            tod(23, 30)
+        );
+        assert_eq!(TimeOfDay::hms(24, 0, 0), None);
+        assert_eq!(TimeOfDay::hms(0, 60, 0), None);
+    }
+
+    #[test]
+    fn test_utc_offset() {
+        assert_eq!(UtcOffset::minutes(30), Some(UtcOffset(1800)));
+        assert_eq!(UtcOffset::minutes(-30), Some(UtcOffset(-1800)));
+        assert_eq!(UtcOffset::minutes(5 * 60 + 45), Some(UtcOffset(20_700)));
+        assert_eq!(UtcOffset::minutes(24 * 60), None);
+        assert_eq!(UtcOffset::minutes(i32::MIN), None);
+        assert_eq!(UtcOffset::hours(-5), Some(UtcOffset(-18_000)));
+        assert_eq!(UtcOffset::hours(-5).map(UtcOffset::seconds), Some(-18_000));
+        assert_eq!(UtcOffset::hours(24), None);
+        assert_eq!(UtcOffset::hours(i32::MIN), None);
+    }
+
+    #[test]
+    fn test_extreme_timestamps() {
+        let max = Timestamp(i64::MAX);
+        let min = Timestamp(i64::MIN);
+
+        let _ = Weekday::of(max, UtcOffset::hours(10).unwrap());
+        let _ = TimeOfDay::of(min, UtcOffset::hours(-10).unwrap());
+
+        let window = TimeCondition::Recurring {
+            start: min,
+            period: 24 * HOUR,
+            duration: HOUR,
+        };
+        assert!(window.holds_at(min));
+        assert!(!window.holds_at(Timestamp(min.0 + HOUR)));
+        let _ = window.holds_at(max);
+    }
+
+    #[test]
+    fn test_time_of_day_window() {
+        let window = TimeCondition::TimeOfDay {
+            offset: UtcOffset::hours(-5).unwrap(),
+            start: tod(9, 0),
+            end: tod(17, 0),
+        };
+
+        // 14:00 UTC is 09:00 at -05:00
+        assert!(window.holds_at(at(14 * HOUR)));
+        assert!(!window.holds_at(at(14 * HOUR - 1)));
+        assert!(window.holds_at(at(22 * HOUR - 1)));
+        assert!(!window.holds_at(at(22 * HOUR)));
+    }
+
+    #[test]
+    fn test_time_of_day_window_spanning_midnight() {
+        let night = TimeCondition::TimeOfDay {
+            offset: UtcOffset::UTC,
+            start: tod(22, 0),
+            end: tod(6, 0),
+        };
+
+        assert!(night.holds_at(at(23 * HOUR)));
+        assert!(night.holds_at(at(2 * HOUR)));
+        assert!(!night.holds_at(at(12 * HOUR)));
+        assert!(!night.holds_at(at(6 * HOUR)));
+    }
+
+    #[test]
+    fn test_recurring() {
+        let window = TimeCondition::Recurring {
+            start: at(0),
+            period: 24 * HOUR,
+            duration: HOUR,
+        };
+
+        assert!(window.holds_at(at(0)));
+        assert!(window.holds_at(at(3 * 24 * HOUR + HOUR - 1)));
+        assert!(!window.holds_at(at(3 * 24 * HOUR + HOUR)));
+        assert!(!window.holds_at(at(-1)));
+
+        let degenerate = TimeCondition::Recurring {
+            start: at(0),
+            period: 0,
+            duration: HOUR,
+        };
+        assert!(!degenerate.holds_at(at(0)));
+    }
+
+    #[test]
+    fn test_combinators() {
+        use TimeCondition::*;
+        let business_hours = TimeCondition::weekdays_between(UtcOffset::UTC, tod(9, 0), tod(17, 0));
+
+        assert!(business_hours.holds_at(at(10 * HOUR)));
+        assert!(!business_hours.holds_at(at(8 * HOUR)));
+        assert!(!business_hours.holds_at(at(5 * 24 * HOUR + 10 * HOUR)));
+        assert!(Not(Box::new(business_hours.clone())).holds_at(at(8 * HOUR)));
+        assert!(Any(vec![business_hours, After(at(0))]).holds_at(at(8 * HOUR)));
+        assert!(All(vec![]).holds_at(at(0)));
+        assert!(!Any(vec![]).holds_at(at(0)));
+    }
+
+    #[test]
+    fn test_clocks() {
+        assert_eq!(FixedClock(Timestamp(5)).now(), Timestamp(5));
+
+        let clock = ManualClock::new(Timestamp(5));
+        clock.advance(10);
+        assert_eq!(clock.now(), Timestamp(15));
+        clock.set(Timestamp(1));
+        assert_eq!(clock.now(), Timestamp(1));
+
+        assert!(SystemClock.now() > Timestamp(MONDAY));
+        assert_eq!(Timestamp::from(UNIX_EPOCH), Timestamp(0));
+    }
+
+    #[test]
+    fn test_clock_environment_resolves_effects() {
+        let clock = ManualClock::new(at(0));
+        let env = ClockEnvironment::new(&clock);
+        let effect = DependentEffect::Aggregate(vec![
+            DependentEffect::Atomic(Effect::ALLOW, TimeCondition::Before(at(HOUR))),
+            DependentEffect::Atomic(
+                Effect::DENY,
+                TimeCondition::DaysOfWeek(UtcOffset::UTC, vec![Weekday::Sun]),
+            ),
+        ]);
+
+        assert_eq!(effect.resolve(&env), Ok(ALLOW));
+
+        clock.advance(HOUR);
+        assert_eq!(effect.resolve(&env), Ok(SILENT));
+
+        clock.set(at(-1));
+        assert_eq!(effect.resolve(&env), Ok(DENY));
+    }
+}